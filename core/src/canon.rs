@@ -0,0 +1,211 @@
+//! Canonical forms of puzzles, to find the same puzzle written differently.
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::thread;
+use std::{cmp::Ordering, collections::HashMap};
+
+use crate::Grid;
+
+/// Digits of a grid with 0 for empty cells, which sorts them first.
+type Cells = [[u8; 9]; 9];
+
+/// Every order of the nine columns that keeps them in their stacks: the
+/// stacks in any order, and the columns of each stack in any order.
+fn arrangements() -> Vec<[usize; 9]> {
+    const PERMUTATIONS: [[usize; 3]; 6] = [
+        [0, 1, 2],
+        [0, 2, 1],
+        [1, 0, 2],
+        [1, 2, 0],
+        [2, 0, 1],
+        [2, 1, 0],
+    ];
+    let mut arrangements = Vec::with_capacity(6 * 6 * 6 * 6);
+    for stacks in PERMUTATIONS {
+        for a in PERMUTATIONS {
+            for b in PERMUTATIONS {
+                for c in PERMUTATIONS {
+                    let mut order = [0; 9];
+                    for (s, within) in [a, b, c].iter().enumerate() {
+                        for (n, column) in within.iter().enumerate() {
+                            order[3 * s + n] = 3 * stacks[s] + column;
+                        }
+                    }
+                    arrangements.push(order);
+                }
+            }
+        }
+    }
+    arrangements
+}
+
+/// Branch and bound over the row orders for one column order.
+///
+/// Rows are picked one at a time, relabelling digits in order of first
+/// appearance, and a branch is dropped as soon as its rows so far sort after
+/// the best grid found yet.
+struct Search<'a> {
+    cells: &'a Cells,
+    columns: [usize; 9],
+    rows: Cells,
+    best: &'a mut Cells,
+}
+
+impl Search<'_> {
+    fn place(&mut self, r: usize, band: usize, used: u16, labels: [u8; 10], next: u8) {
+        // a new band may come from any unused band, otherwise stay in the band
+        let candidates = if r.is_multiple_of(3) {
+            0..9
+        } else {
+            3 * band..3 * band + 3
+        };
+        for source in candidates {
+            if used & (1 << source) != 0
+                || (r.is_multiple_of(3) && used & (0b111 << (source / 3 * 3)) != 0)
+            {
+                continue;
+            }
+            let (mut labels, mut next) = (labels, next);
+            // while the rows so far tie with the best, stop at the first
+            // cell that sorts after it
+            let mut order = if self.rows[..r] == self.best[..r] {
+                Ordering::Equal
+            } else {
+                Ordering::Less
+            };
+            for (n, &column) in self.columns.iter().enumerate() {
+                let k = self.cells[source][column] as usize;
+                if k != 0 && labels[k] == 0 {
+                    labels[k] = next;
+                    next += 1;
+                }
+                self.rows[r][n] = labels[k];
+                if order == Ordering::Equal {
+                    order = labels[k].cmp(&self.best[r][n]);
+                    if order == Ordering::Greater {
+                        break;
+                    }
+                }
+            }
+            match order {
+                Ordering::Greater => continue,
+                _ if r == 8 => *self.best = self.rows,
+                _ => self.place(r + 1, source / 3, used | 1 << source, labels, next),
+            }
+        }
+    }
+}
+
+/// The smallest form of a puzzle under the symmetries of sudoku: relabelling
+/// digits, reordering rows within bands and bands, likewise for columns and
+/// stacks, and transposing.
+///
+/// Cells are compared row by row with empty cells first.
+pub fn canonical(grid: &Grid) -> Grid {
+    let cells: Cells = grid.map(|row| row.map(|cell| cell.unwrap_or(0)));
+    let mut transposed = [[0; 9]; 9];
+    for (i, row) in cells.iter().enumerate() {
+        for (j, k) in row.iter().enumerate() {
+            transposed[j][i] = *k;
+        }
+    }
+    let mut best = [[u8::MAX; 9]; 9];
+    let arrangements = arrangements();
+    for cells in [&cells, &transposed] {
+        for columns in &arrangements {
+            let mut search = Search {
+                cells,
+                columns: *columns,
+                rows: [[0; 9]; 9],
+                best: &mut best,
+            };
+            // labels are indexed by digit, 0 keeps mapping to empty
+            search.place(0, 0, 0, [0; 10], 1);
+        }
+    }
+    best.map(|row| row.map(|k| Some(k).filter(|&k| k != 0)))
+}
+
+/// Whether two puzzles are the same up to the symmetries of sudoku.
+pub fn equivalent(a: &Grid, b: &Grid) -> bool {
+    canonical(a) == canonical(b)
+}
+
+/// Canonical forms of many puzzles, spread over all CPUs.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn canonical_all(grids: &[Grid]) -> Vec<Grid> {
+    let jobs = thread::available_parallelism().map_or(1, usize::from);
+    let chunk = grids.len().div_ceil(jobs).max(1);
+    thread::scope(|scope| {
+        let handles: Vec<_> = grids
+            .chunks(chunk)
+            .map(|chunk| scope.spawn(move || chunk.iter().map(canonical).collect::<Vec<_>>()))
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("canonical form panicked"))
+            .collect()
+    })
+}
+
+/// Canonical forms of many puzzles, one after the other on wasm32, which has
+/// no threads.
+#[cfg(target_arch = "wasm32")]
+pub fn canonical_all(grids: &[Grid]) -> Vec<Grid> {
+    grids.iter().map(canonical).collect()
+}
+
+/// For each puzzle, the first earlier puzzle equivalent to it.
+pub fn duplicates(grids: &[Grid]) -> Vec<Option<usize>> {
+    let mut first = HashMap::new();
+    canonical_all(grids)
+        .into_iter()
+        .enumerate()
+        .map(|(index, canonical)| {
+            let original = *first.entry(canonical).or_insert(index);
+            (original != index).then_some(original)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::format::{format_grid, parse_grid};
+
+    #[test]
+    fn test_canonical() {
+        let grid = parse_grid(
+            "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79",
+        )
+        .unwrap();
+        // transpose, swap the first two bands, and swap digits 1 and 2
+        let mut other = [[None; 9]; 9];
+        for i in 0..9 {
+            for j in 0..9 {
+                let band = [1, 0, 2][i / 3];
+                other[3 * band + i % 3][j] = grid[j][i].map(|k| match k {
+                    1 => 2,
+                    2 => 1,
+                    k => k,
+                });
+            }
+        }
+        assert!(equivalent(&grid, &other));
+        let canonical = canonical(&grid);
+        assert_eq!(
+            format_grid(&canonical),
+            "........1.....2.34156...27......8.......51..232974......16..9....34..7..28.91.45."
+        );
+        assert_eq!(super::canonical(&canonical), canonical);
+
+        let mut different = grid;
+        different[0][2] = Some(4);
+        assert!(!equivalent(&grid, &different));
+        assert_eq!(
+            duplicates(&[grid, different, other]),
+            vec![None, None, Some(0)]
+        );
+    }
+}
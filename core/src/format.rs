@@ -0,0 +1,245 @@
+//! Puzzles as text: single grids, pasted tables and collection files.
+
+use std::fmt;
+
+use crate::Grid;
+
+/// Why some text is not a puzzle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// A grid with another number of cells than 81.
+    Length(usize),
+    /// A character that is neither a digit nor an empty cell.
+    Character(char),
+    /// Text without a single cell.
+    Empty,
+    /// A line of a collection, counted from 1, that is not a puzzle.
+    Line(usize, Box<ParseError>),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Length(count) => write!(f, "expected 81 cells, found {}", count),
+            ParseError::Character(c) => write!(f, "unexpected character `{}`", c),
+            ParseError::Empty => write!(f, "no puzzle found"),
+            ParseError::Line(line, _) => write!(f, "line {}", line),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ParseError::Line(_, err) => Some(err.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+/// A puzzle of a collection file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub grid: Grid,
+    /// Whatever follows the puzzle on its line, e.g. a name or rating.
+    pub meta: String,
+}
+
+impl Entry {
+    /// Number of given digits.
+    pub fn clues(&self) -> usize {
+        self.grid
+            .iter()
+            .flatten()
+            .filter(|cell| cell.is_some())
+            .count()
+    }
+}
+
+/// Reads a grid written as 81 characters row by row, with `.`, `0`, `-` or `_`
+/// for empty cells.
+pub fn parse_grid(s: &str) -> Result<Grid, ParseError> {
+    let count = s.chars().count();
+    if count != 81 {
+        return Err(ParseError::Length(count));
+    }
+    let mut grid = [[None; 9]; 9];
+    for (n, c) in s.chars().enumerate() {
+        grid[n / 9][n % 9] = match c {
+            '1'..='9' => Some(c as u8 - b'0'),
+            '.' | '0' | '-' | '_' => None,
+            c => return Err(ParseError::Character(c)),
+        };
+    }
+    Ok(grid)
+}
+
+/// Writes a grid as 81 characters row by row, with `.` for empty cells.
+pub fn format_grid(grid: &Grid) -> String {
+    grid.iter()
+        .flatten()
+        .map(|cell| cell.map_or('.', |k| (b'0' + k) as char))
+        .collect()
+}
+
+/// Writes a grid as nine rows with `|` and `-` between the boxes.
+pub fn format_table(grid: &Grid) -> String {
+    let mut lines = Vec::new();
+    for (i, row) in grid.iter().enumerate() {
+        if i == 3 || i == 6 {
+            lines.push("-------+-------+------".to_string());
+        }
+        let cells: Vec<String> = row
+            .chunks(3)
+            .map(|band| {
+                band.iter()
+                    .map(|cell| cell.map_or(".".to_string(), |k| k.to_string()))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect();
+        lines.push(format!(" {}", cells.join(" | ")));
+    }
+    lines.join("\n")
+}
+
+/// Splits a line of a collection into the puzzle and its metadata.
+///
+/// The puzzle is the first field of a line; the rest, after whitespace, `,`,
+/// `;` or `|`, is kept as metadata. Blank lines and `#` comments give `None`.
+pub fn split_line(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let separator = |c: char| c.is_whitespace() || matches!(c, ',' | ';' | '|');
+    let (puzzle, meta) = line.split_once(separator).unwrap_or((line, ""));
+    Some((puzzle, meta.trim_matches(separator)))
+}
+
+/// Reads a single puzzle as pasted: 81 characters on a line, or nine rows
+/// of a grid with any `|`, `+`, `-` and spaces between cells, as in `.sdk`
+/// files, whose `#` comments and `[Puzzle]` header are skipped.
+pub fn parse_puzzle(text: &str) -> Result<Grid, ParseError> {
+    let lines: Vec<&str> = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect();
+    if let [line] = lines[..] {
+        if let Some((puzzle, _)) = split_line(line).filter(|(p, _)| p.chars().count() == 81) {
+            return parse_grid(puzzle);
+        }
+    }
+    let mut cells = String::new();
+    for line in lines {
+        if line.starts_with('[') {
+            // the sections after the puzzle hold the state of a game
+            if line.eq_ignore_ascii_case("[puzzle]") {
+                continue;
+            }
+            break;
+        }
+        for c in line.chars() {
+            match c {
+                '1'..='9' | '.' | '0' | '_' => cells.push(c),
+                '|' | '+' | '-' | '=' => {}
+                c if c.is_whitespace() => {}
+                c => return Err(ParseError::Character(c)),
+            }
+        }
+    }
+    if cells.is_empty() {
+        return Err(ParseError::Empty);
+    }
+    parse_grid(&cells)
+}
+
+/// Reads a collection, one puzzle per line, skipping blank lines and `#`
+/// comments.
+pub fn parse_collection(content: &str) -> Result<Vec<Entry>, ParseError> {
+    let mut entries = Vec::new();
+    for (n, line) in content.lines().enumerate() {
+        let Some((puzzle, meta)) = split_line(line) else {
+            continue;
+        };
+        let grid = parse_grid(puzzle).map_err(|err| ParseError::Line(n + 1, Box::new(err)))?;
+        entries.push(Entry {
+            grid,
+            meta: meta.to_string(),
+        });
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod test {
+    use std::error::Error;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_collection() {
+        let content = "\
+# easy ones
+53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79 first, rated 1.2
+
+003020600900305001001806400008102900700000008006708200002609500800203009005010300
+";
+        let entries = parse_collection(content).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].grid[0][0], Some(5));
+        assert_eq!(entries[0].grid[0][2], None);
+        assert_eq!(entries[0].meta, "first, rated 1.2");
+        assert_eq!(entries[0].clues(), 30);
+        assert_eq!(entries[1].meta, "");
+
+        let err = parse_collection("\n123").unwrap_err();
+        assert_eq!(err.to_string(), "line 2");
+        assert_eq!(
+            err.source().unwrap().to_string(),
+            "expected 81 cells, found 3"
+        );
+    }
+
+    #[test]
+    fn test_parse_puzzle() {
+        let line =
+            "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+        let grid = parse_grid(line).unwrap();
+        assert_eq!(parse_puzzle(&format!("  {} first\n", line)).unwrap(), grid);
+
+        let table = "\
+5 3 . | . 7 . | . . .
+6 . . | 1 9 5 | . . .
+. 9 8 | . . . | . 6 .
+------+-------+------
+8 . . | . 6 . | . . 3
+4 . . | 8 . 3 | . . 1
+7 . . | . 2 . | . . 6
+------+-------+------
+. 6 . | . . . | 2 8 .
+. . . | 4 1 9 | . . 5
+. . . | . 8 . | . 7 9
+";
+        assert_eq!(parse_puzzle(table).unwrap(), grid);
+
+        let mut sdk = String::from("#Afrom a book\n[Puzzle]\n");
+        for row in 0..9 {
+            sdk += &line[9 * row..9 * row + 9];
+            sdk += "\n";
+        }
+        sdk += "[State]\n534678912\n";
+        assert_eq!(parse_puzzle(&sdk).unwrap(), grid);
+
+        assert_eq!(
+            parse_puzzle("hello").unwrap_err().to_string(),
+            "unexpected character `h`"
+        );
+        assert_eq!(
+            parse_puzzle("12345\n6789").unwrap_err().to_string(),
+            "expected 81 cells, found 9"
+        );
+        assert_eq!(parse_puzzle("# nothing\n"), Err(ParseError::Empty));
+    }
+}
@@ -0,0 +1,88 @@
+//! New puzzles, and how hard they are.
+
+use std::fmt;
+
+use crate::{native, Grid};
+
+/// How hard a puzzle is, by how much searching the native solver needs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Difficulty {
+    /// Naked and hidden singles alone solve it.
+    Easy,
+    /// A few guesses are needed.
+    Medium,
+    Hard,
+}
+
+/// Search nodes up to which a puzzle that needs guessing counts as medium.
+const MEDIUM_NODES: u64 = 20;
+
+/// Rates a puzzle by the nodes the native solver visits to prove its
+/// solution unique.
+pub fn rate(grid: &Grid) -> Difficulty {
+    match native::search(grid, 2).nodes {
+        0 | 1 => Difficulty::Easy,
+        nodes if nodes <= MEDIUM_NODES => Difficulty::Medium,
+        _ => Difficulty::Hard,
+    }
+}
+
+/// A random puzzle with a unique solution, from which no given can be
+/// taken away without losing that.
+pub fn generate() -> Grid {
+    let mut grid = native::random_grid();
+    let mut cells: Vec<usize> = (0..81).collect();
+    fastrand::shuffle(&mut cells);
+    for n in cells {
+        let (row, column) = (n / 9, n % 9);
+        let given = grid[row][column].take();
+        if native::count(&grid, 2) != 1 {
+            grid[row][column] = given;
+        }
+    }
+    grid
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Difficulty::Easy => write!(f, "easy"),
+            Difficulty::Medium => write!(f, "medium"),
+            Difficulty::Hard => write!(f, "hard"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::{format::parse_grid, logic};
+
+    #[test]
+    fn test_generate() {
+        assert!(logic::is_solved(&native::random_grid()));
+
+        let grid = generate();
+        assert_eq!(native::count(&grid, 2), 1);
+        // minimal: every given is needed
+        for (row, column) in (0..81).map(|n| (n / 9, n % 9)) {
+            if grid[row][column].is_some() {
+                let mut fewer = grid;
+                fewer[row][column] = None;
+                assert_eq!(native::count(&fewer, 2), 2);
+            }
+        }
+
+        let easy = parse_grid(
+            "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79",
+        )
+        .unwrap();
+        assert_eq!(rate(&easy), Difficulty::Easy);
+        let hard = parse_grid(
+            "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..",
+        )
+        .unwrap();
+        assert_eq!(rate(&hard), Difficulty::Hard);
+    }
+}
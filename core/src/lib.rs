@@ -0,0 +1,40 @@
+//! Reading, solving and checking classic 9x9 sudoku puzzles.
+//!
+//! A puzzle is a [`Grid`], read from text by the [`format`](mod@format) module. The
+//! [`solver`] solves it with one of two backends: SCIP, run on an
+//! [`LpModel`](solver::LpModel) of the cells that propagation leaves open, or
+//! the built-in [`native`] search. Any solution a backend returns is checked
+//! with [`verify`] first. [`logic`] finds candidates and the next naked or
+//! hidden single, [`generate`] makes and rates new puzzles and [`canon`]
+//! tells equivalent puzzles apart.
+//!
+//! ```
+//! use sudoku_core::{
+//!     format::{format_grid, parse_grid},
+//!     solver::{solve, Backend, SolverConfig},
+//! };
+//!
+//! let puzzle = parse_grid(
+//!     "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79",
+//! )?;
+//! let config = SolverConfig {
+//!     backend: Backend::Native,
+//!     ..SolverConfig::default()
+//! };
+//! let solution = solve(&puzzle, &config)?;
+//! assert!(format_grid(&solution).starts_with("534678912"));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+pub mod canon;
+pub mod format;
+pub mod generate;
+pub mod logic;
+pub mod native;
+pub mod solver;
+pub mod stats;
+pub mod verify;
+
+/// A puzzle or solution, row by row, with `None` for empty cells and digits
+/// from 1 to 9.
+pub type Grid = [[Option<u8>; 9]; 9];
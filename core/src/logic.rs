@@ -0,0 +1,178 @@
+//! Candidates and the steps a person would take: naked and hidden singles.
+
+use std::fmt;
+
+use crate::{verify, Grid};
+
+/// Bit mask with bit `k` set for every digit `k` from 1 to 9.
+pub const ALL: u16 = 0b11_1111_1110;
+
+/// A row, column or 3x3 box of the grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    Row(usize),
+    Column(usize),
+    Box(usize),
+}
+
+/// Why a digit has to go into a cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reason {
+    /// It is the only candidate left in the cell.
+    NakedSingle,
+    /// It is the only place left for the digit in the unit.
+    HiddenSingle(Unit),
+}
+
+/// A digit that can be placed by logic alone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Step {
+    pub row: usize,
+    pub column: usize,
+    pub digit: u8,
+    pub reason: Reason,
+}
+
+impl Unit {
+    /// All 27 units of the grid.
+    pub fn all() -> impl Iterator<Item = Unit> {
+        (0..9)
+            .map(Unit::Row)
+            .chain((0..9).map(Unit::Column))
+            .chain((0..9).map(Unit::Box))
+    }
+
+    /// The (row, column) pairs of the cells in this unit.
+    pub fn cells(self) -> impl Iterator<Item = (usize, usize)> {
+        (0..9).map(move |n| match self {
+            Unit::Row(r) => (r, n),
+            Unit::Column(c) => (n, c),
+            Unit::Box(b) => (3 * (b / 3) + n / 3, 3 * (b % 3) + n % 3),
+        })
+    }
+}
+
+/// Digits that can still go into each empty cell; filled cells have none.
+pub fn candidates(grid: &Grid) -> [[u16; 9]; 9] {
+    let mut used = [[0u16; 9]; 3];
+    for (r, row) in grid.iter().enumerate() {
+        for (c, cell) in row.iter().enumerate() {
+            if let Some(k) = cell {
+                used[0][r] |= 1 << k;
+                used[1][c] |= 1 << k;
+                used[2][3 * (r / 3) + c / 3] |= 1 << k;
+            }
+        }
+    }
+    let mut candidates = [[0; 9]; 9];
+    for (r, row) in grid.iter().enumerate() {
+        for (c, cell) in row.iter().enumerate() {
+            if cell.is_none() {
+                candidates[r][c] = ALL & !(used[0][r] | used[1][c] | used[2][3 * (r / 3) + c / 3]);
+            }
+        }
+    }
+    candidates
+}
+
+/// The next digit that follows from a naked or hidden single, if any.
+pub fn next_step(grid: &Grid) -> Option<Step> {
+    let candidates = candidates(grid);
+    for (row, cells) in candidates.iter().enumerate() {
+        for (column, mask) in cells.iter().enumerate() {
+            if mask.count_ones() == 1 {
+                return Some(Step {
+                    row,
+                    column,
+                    digit: mask.trailing_zeros() as u8,
+                    reason: Reason::NakedSingle,
+                });
+            }
+        }
+    }
+    for unit in Unit::all() {
+        for digit in 1..=9u8 {
+            let mut places = unit
+                .cells()
+                .filter(|&(r, c)| candidates[r][c] & (1 << digit) != 0);
+            if let (Some((row, column)), None) = (places.next(), places.next()) {
+                return Some(Step {
+                    row,
+                    column,
+                    digit,
+                    reason: Reason::HiddenSingle(unit),
+                });
+            }
+        }
+    }
+    None
+}
+
+/// Whether every cell is filled without conflicts.
+pub fn is_solved(grid: &Grid) -> bool {
+    grid.iter().flatten().all(Option::is_some) && verify::conflicts(grid).is_empty()
+}
+
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Unit::Row(r) => write!(f, "row {}", r + 1),
+            Unit::Column(c) => write!(f, "column {}", c + 1),
+            Unit::Box(b) => write!(f, "box {}", b + 1),
+        }
+    }
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "R{}C{} = {}", self.row + 1, self.column + 1, self.digit)?;
+        match self.reason {
+            Reason::NakedSingle => write!(f, ", the only candidate left in the cell"),
+            Reason::HiddenSingle(unit) => {
+                write!(f, ", the only place for {} in {}", self.digit, unit)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_next_step() {
+        let mut grid = [[None; 9]; 9];
+        for (c, cell) in grid[0].iter_mut().take(8).enumerate() {
+            *cell = Some(c as u8 + 1);
+        }
+        let step = next_step(&grid).unwrap();
+        assert_eq!((step.row, step.column, step.digit), (0, 8, 9));
+        assert_eq!(step.reason, Reason::NakedSingle);
+
+        // 1 is blocked from every cell of row 2 but R2C1
+        let mut grid = [[None; 9]; 9];
+        grid[0][4] = Some(1);
+        grid[2][7] = Some(1);
+        grid[5][1] = Some(1);
+        grid[7][2] = Some(1);
+        let step = next_step(&grid).unwrap();
+        assert_eq!((step.row, step.column, step.digit), (1, 0, 1));
+        assert_eq!(step.reason, Reason::HiddenSingle(Unit::Row(1)));
+    }
+
+    #[test]
+    fn test_solved() {
+        let mut grid = [[None; 9]; 9];
+        for (r, row) in grid.iter_mut().enumerate() {
+            for (c, cell) in row.iter_mut().enumerate() {
+                *cell = Some(((3 * r + r / 3 + c) % 9) as u8 + 1);
+            }
+        }
+        assert!(is_solved(&grid));
+        grid[0].swap(0, 1);
+        assert!(!is_solved(&grid));
+        grid[0].swap(0, 1);
+        grid[8][8] = None;
+        assert!(!is_solved(&grid));
+    }
+}
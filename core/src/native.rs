@@ -0,0 +1,308 @@
+//! The built-in solver: propagation of singles on bitmask candidates and a
+//! search that branches on the cell with the fewest candidates.
+
+use crate::{logic::ALL, Grid};
+
+/// Cells of the 27 units, indexed `9 * row + column`.
+const UNITS: [[u8; 9]; 27] = units();
+
+const fn units() -> [[u8; 9]; 27] {
+    let mut units = [[0; 9]; 27];
+    let mut u = 0;
+    while u < 9 {
+        let mut n = 0;
+        while n < 9 {
+            units[u][n] = (9 * u + n) as u8;
+            units[9 + u][n] = (9 * n + u) as u8;
+            units[18 + u][n] = (9 * (3 * (u / 3) + n / 3) + 3 * (u % 3) + n % 3) as u8;
+            n += 1;
+        }
+        u += 1;
+    }
+    units
+}
+
+/// What a search found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Outcome {
+    /// The first solution found.
+    pub solution: Option<Grid>,
+    /// Solutions found, at most the limit of the search.
+    pub count: usize,
+    /// Search nodes visited, one per board propagated.
+    pub nodes: u64,
+}
+
+/// A puzzle after placing every single that follows from its givens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Propagated {
+    pub grid: Grid,
+    /// Digits left for each empty cell, bit `k` set for digit `k`.
+    pub candidates: [[u16; 9]; 9],
+}
+
+/// Digits placed so far, with the digits used by every row, column and box.
+#[derive(Clone, Copy)]
+struct Board {
+    cells: [u8; 81],
+    rows: [u16; 9],
+    columns: [u16; 9],
+    boxes: [u16; 9],
+}
+
+impl Board {
+    /// The board of a puzzle, `None` if two givens clash.
+    fn new(grid: &Grid) -> Option<Self> {
+        let mut board = Board {
+            cells: [0; 81],
+            rows: [0; 9],
+            columns: [0; 9],
+            boxes: [0; 9],
+        };
+        for (n, cell) in grid.iter().flatten().enumerate() {
+            if let Some(k) = *cell {
+                if board.candidates(n) & (1 << k) == 0 {
+                    return None;
+                }
+                board.place(n, k);
+            }
+        }
+        Some(board)
+    }
+
+    fn candidates(&self, n: usize) -> u16 {
+        let (r, c) = (n / 9, n % 9);
+        ALL & !(self.rows[r] | self.columns[c] | self.boxes[3 * (r / 3) + c / 3])
+    }
+
+    fn place(&mut self, n: usize, k: u8) {
+        let (r, c) = (n / 9, n % 9);
+        self.cells[n] = k;
+        self.rows[r] |= 1 << k;
+        self.columns[c] |= 1 << k;
+        self.boxes[3 * (r / 3) + c / 3] |= 1 << k;
+    }
+
+    /// Places naked and hidden singles until none are left.
+    ///
+    /// Returns `false` on a contradiction: a cell without candidates, or a
+    /// digit without a place in some unit.
+    fn propagate(&mut self) -> bool {
+        loop {
+            let mut progress = false;
+            for n in 0..81 {
+                if self.cells[n] != 0 {
+                    continue;
+                }
+                let candidates = self.candidates(n);
+                match candidates.count_ones() {
+                    0 => return false,
+                    1 => {
+                        self.place(n, candidates.trailing_zeros() as u8);
+                        progress = true;
+                    }
+                    _ => {}
+                }
+            }
+            // naked singles are cheaper to find, so exhaust them first
+            if progress {
+                continue;
+            }
+            for unit in &UNITS {
+                let (mut once, mut twice, mut placed) = (0u16, 0u16, 0u16);
+                for &n in unit {
+                    let n = n as usize;
+                    if self.cells[n] != 0 {
+                        placed |= 1 << self.cells[n];
+                    } else {
+                        let candidates = self.candidates(n);
+                        twice |= once & candidates;
+                        once |= candidates;
+                    }
+                }
+                if once | placed != ALL {
+                    return false;
+                }
+                let mut singles = once & !twice & !placed;
+                while singles != 0 {
+                    let k = singles.trailing_zeros() as u8;
+                    singles &= singles - 1;
+                    // an earlier single of this unit may have taken the place
+                    let Some(&n) = unit.iter().find(|&&n| {
+                        self.cells[n as usize] == 0 && self.candidates(n as usize) & (1 << k) != 0
+                    }) else {
+                        return false;
+                    };
+                    self.place(n as usize, k);
+                    progress = true;
+                }
+            }
+            if !progress {
+                return true;
+            }
+        }
+    }
+
+    /// The empty cell with the fewest candidates, `None` if the board is full.
+    fn branch_cell(&self) -> Option<(usize, u16)> {
+        let mut best: Option<(usize, u16)> = None;
+        for n in (0..81).filter(|&n| self.cells[n] == 0) {
+            let candidates = self.candidates(n);
+            if best.is_none_or(|(_, b)| candidates.count_ones() < b.count_ones()) {
+                best = Some((n, candidates));
+                // rarely beaten, and scanning on costs more than it saves
+                if candidates.count_ones() <= 2 {
+                    break;
+                }
+            }
+        }
+        best
+    }
+
+    fn grid(&self) -> Grid {
+        let mut grid = [[None; 9]; 9];
+        for (n, &k) in self.cells.iter().enumerate() {
+            grid[n / 9][n % 9] = Some(k).filter(|&k| k != 0);
+        }
+        grid
+    }
+}
+
+fn search_from(mut board: Board, limit: usize, outcome: &mut Outcome) {
+    outcome.nodes += 1;
+    if !board.propagate() {
+        return;
+    }
+    let Some((n, mut candidates)) = board.branch_cell() else {
+        outcome.count += 1;
+        outcome.solution.get_or_insert_with(|| board.grid());
+        return;
+    };
+    while candidates != 0 && outcome.count < limit {
+        let k = candidates.trailing_zeros() as u8;
+        candidates &= candidates - 1;
+        let mut next = board;
+        next.place(n, k);
+        search_from(next, limit, outcome);
+    }
+}
+
+/// Places naked and hidden singles until none are left, `None` if that
+/// runs into a contradiction.
+pub fn propagate(grid: &Grid) -> Option<Propagated> {
+    let mut board = Board::new(grid)?;
+    if !board.propagate() {
+        return None;
+    }
+    let mut candidates = [[0; 9]; 9];
+    for n in (0..81).filter(|&n| board.cells[n] == 0) {
+        candidates[n / 9][n % 9] = board.candidates(n);
+    }
+    Some(Propagated {
+        grid: board.grid(),
+        candidates,
+    })
+}
+
+impl Propagated {
+    /// Whether propagation alone filled every cell.
+    pub fn is_solved(&self) -> bool {
+        self.grid.iter().flatten().all(Option::is_some)
+    }
+}
+
+/// Searches for up to `limit` solutions.
+pub fn search(grid: &Grid, limit: usize) -> Outcome {
+    let mut outcome = Outcome {
+        solution: None,
+        count: 0,
+        nodes: 0,
+    };
+    if let Some(board) = Board::new(grid) {
+        search_from(board, limit, &mut outcome);
+    }
+    outcome
+}
+
+/// A complete grid picked at random, by searching with the digits of every
+/// branch in random order.
+pub fn random_grid() -> Grid {
+    fn fill(mut board: Board) -> Option<Board> {
+        if !board.propagate() {
+            return None;
+        }
+        let Some((n, candidates)) = board.branch_cell() else {
+            return Some(board);
+        };
+        let mut digits: Vec<u8> = (1..=9).filter(|k| candidates & (1 << k) != 0).collect();
+        fastrand::shuffle(&mut digits);
+        digits.into_iter().find_map(|k| {
+            let mut next = board;
+            next.place(n, k);
+            fill(next)
+        })
+    }
+    let empty = Board::new(&[[None; 9]; 9]).expect("the empty grid has no clashes");
+    fill(empty).expect("the empty grid has solutions").grid()
+}
+
+/// Counts the solutions of a puzzle, stopping once `limit` are found.
+pub fn count(grid: &Grid, limit: usize) -> usize {
+    search(grid, limit).count
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::format::{format_grid, parse_grid};
+
+    #[test]
+    fn test_search() {
+        // one of the hardest known puzzles for backtracking solvers
+        let grid = parse_grid(
+            "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..",
+        )
+        .unwrap();
+        let outcome = search(&grid, 2);
+        assert_eq!(outcome.count, 1);
+        assert_eq!(
+            format_grid(&outcome.solution.unwrap()),
+            "812753649943682175675491283154237896369845721287169534521974368438526917796318452"
+        );
+
+        assert_eq!(count(&[[None; 9]; 9], 2), 2);
+        let mut grid = [[None; 9]; 9];
+        grid[0][0] = Some(1);
+        grid[0][1] = Some(1);
+        assert_eq!(count(&grid, 2), 0);
+        // nothing fits R1C1
+        let mut grid = [[None; 9]; 9];
+        for (c, cell) in grid[0].iter_mut().enumerate().skip(1) {
+            *cell = Some(c as u8);
+        }
+        grid[1][0] = Some(9);
+        let outcome = search(&grid, 2);
+        assert_eq!((outcome.count, outcome.nodes), (0, 1));
+        assert_eq!(propagate(&grid), None);
+    }
+
+    #[test]
+    fn test_propagate() {
+        let easy = parse_grid(
+            "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79",
+        )
+        .unwrap();
+        assert!(propagate(&easy).unwrap().is_solved());
+
+        let mut grid = [[None; 9]; 9];
+        for (c, cell) in grid[0].iter_mut().enumerate().take(7) {
+            *cell = Some(c as u8 + 1);
+        }
+        let propagated = propagate(&grid).unwrap();
+        assert!(!propagated.is_solved());
+        assert_eq!(propagated.grid[0][7], None);
+        assert_eq!(propagated.candidates[0][7], 1 << 8 | 1 << 9);
+        assert_eq!(propagated.candidates[0][0], 0);
+    }
+}
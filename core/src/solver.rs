@@ -1,183 +1,500 @@
+//! Solving with a configured backend, SCIP or native, and checking the result.
+
+use std::{collections::BTreeMap, fmt, io, path::PathBuf, string::FromUtf8Error, time::Duration};
+// wasm32 has neither threads nor a clock
+#[cfg(feature = "scip")]
+use std::{
+    fs::{self, File},
+    io::{BufRead, BufReader, Read, Write},
+    process::{Command, Stdio},
+};
+#[cfg(not(target_arch = "wasm32"))]
 use std::{
-    env, fs::File, io::Write, process::Command, sync::mpsc, thread, time::{Duration, Instant}
+    sync::mpsc::{self, TryRecvError},
+    thread,
+    time::Instant,
 };
 
-use color_eyre::Result;
 use regex::Regex;
+use serde::Deserialize;
+#[cfg(feature = "scip")]
 use tempfile::tempdir;
 
+use crate::{
+    native,
+    stats::Stats,
+    verify::{verify, VerifyError},
+    Grid,
+};
+
+#[cfg(all(feature = "scip", target_arch = "wasm32"))]
+compile_error!("the `scip` feature runs processes, which wasm32 doesn't have");
+
+/// How puzzles are solved, by default with SCIP when the `scip` feature is
+/// on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    /// The SCIP executable, run on an LP model of the puzzle.
+    #[cfg(feature = "scip")]
+    #[default]
+    Scip,
+    /// The built-in bitmask solver, much faster but without solver output.
+    #[cfg_attr(not(feature = "scip"), default)]
+    Native,
+}
+
+/// Which backend solves and how, as in the `[solver]` section of the config.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SolverConfig {
+    pub backend: Backend,
+    /// Solver executable, `SCIP_PATH` is used when unset.
+    pub path: Option<PathBuf>,
+    /// Time limit in seconds.
+    pub time_limit: Option<f64>,
+    /// Extra parameters handed to the solver, e.g. `"limits/gap" = 0.01`.
+    pub params: BTreeMap<String, toml::Value>,
+}
+
+impl SolverConfig {
+    /// The solver executable to run, if one is configured.
+    pub fn executable(&self) -> Option<PathBuf> {
+        self.path
+            .clone()
+            .or_else(|| std::env::var_os("SCIP_PATH").map(PathBuf::from))
+    }
+
+    /// Parameters in the format of a SCIP settings file.
+    pub fn settings(&self) -> String {
+        let mut settings = String::new();
+        if let Some(limit) = self.time_limit {
+            settings += &format!("limits/time = {}\n", limit);
+        }
+        for (name, value) in &self.params {
+            let value = match value {
+                toml::Value::Boolean(b) => if *b { "TRUE" } else { "FALSE" }.to_string(),
+                toml::Value::String(s) => format!("\"{}\"", s),
+                v => v.to_string(),
+            };
+            settings += &format!("{} = {}\n", name, value);
+        }
+        settings
+    }
+}
+
+/// The binary program SCIP solves: a variable `xijk` for digit `k` in row
+/// `i` and column `j`, and for every cell, and every digit of each row,
+/// column and box, a constraint that exactly one of its variables is set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LpModel {
+    pub variables: Vec<String>,
+    /// The variables of each constraint, which sum to 1.
+    pub constraints: Vec<Vec<String>>,
+}
+
+impl LpModel {
+    /// The model of the digits still open in `candidates`, which have bit `k`
+    /// set for every digit `k` a cell may take and none for filled cells.
+    pub fn new(candidates: &[[u16; 9]; 9]) -> Self {
+        let open = |i: usize, j: usize, k: u8| candidates[i][j] & (1 << k) != 0;
+        let var = |(i, j, k): (usize, usize, u8)| format!("x{}{}{}", i, j, k);
+
+        let mut constraints: Vec<Vec<String>> = Vec::new();
+        // only one number can be assigned per cell
+        for i in 0..9 {
+            for j in 0..9 {
+                constraints.push(
+                    (1..=9)
+                        .filter(|&k| open(i, j, k))
+                        .map(|k| var((i, j, k)))
+                        .collect(),
+                );
+            }
+        }
+
+        // each number is exactly once in a row
+        for i in 0..9 {
+            for k in 1..=9 {
+                constraints.push(
+                    (0..9)
+                        .filter(|&j| open(i, j, k))
+                        .map(|j| var((i, j, k)))
+                        .collect(),
+                );
+            }
+        }
+
+        // each number is exactly once in a column
+        for j in 0..9 {
+            for k in 1..=9 {
+                constraints.push(
+                    (0..9)
+                        .filter(|&i| open(i, j, k))
+                        .map(|i| var((i, j, k)))
+                        .collect(),
+                );
+            }
+        }
+
+        // each 3x3 square must have all numbers
+        for i in 0..3 {
+            for j in 0..3 {
+                for k in 1..=9 {
+                    constraints.push(
+                        (0..3)
+                            .flat_map(|x| (0..3).map(move |y| (3 * i + x, 3 * j + y, k)))
+                            .filter(|&(i, j, k)| open(i, j, k))
+                            .map(var)
+                            .collect(),
+                    );
+                }
+            }
+        }
+
+        // filled cells and placed numbers leave empty rows behind
+        constraints.retain(|vars| !vars.is_empty());
+        let variables = (0..9)
+            .flat_map(|i| (0..9).flat_map(move |j| (1..=9).map(move |k| (i, j, k))))
+            .filter(|&(i, j, k)| open(i, j, k))
+            .map(var)
+            .collect();
+        Self {
+            variables,
+            constraints,
+        }
+    }
+}
+
+/// Writes the model in the LP file format.
+impl fmt::Display for LpModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Maximize\n\t0")?;
+        writeln!(f, "Subject To")?;
+        for vars in &self.constraints {
+            writeln!(f, "{} = 1", vars.join(" + "))?;
+        }
+        // binary var
+        writeln!(f, "BINARY\n\t{}", self.variables.join(" "))?;
+        writeln!(f, "END")
+    }
+}
+
+/// A solution with the time it took, counted from the start of the solver
+/// thread.
+pub type SolverResult = Result<(Duration, Grid), SolverError>;
+
+/// What the solver thread reports back.
+#[derive(Debug)]
+pub enum SolverMessage {
+    /// A line printed by the solver.
+    Line(String),
+    /// Statistics of the finished run, sent before the result.
+    Stats(Stats),
+    /// The last message of a run.
+    Done(SolverResult),
+}
+
+/// A solve running on its own thread, whose messages are picked up with
+/// [`SolverHandler::try_get`]. Not on wasm32, which has no threads.
+#[cfg(not(target_arch = "wasm32"))]
 #[derive(Debug)]
 pub struct SolverHandler {
-    receiver: mpsc::Receiver<Result<(Duration, [[Option<u8>; 9]; 9]), SolverError>>,
+    receiver: mpsc::Receiver<SolverMessage>,
     #[allow(dead_code)]
     handler: thread::JoinHandle<()>,
 }
 
+/// Why a backend gave no solution.
 #[derive(Debug)]
 pub enum SolverError {
+    /// The puzzle has no solution.
     Infeasible,
+    /// The solver stopped at `solver.time_limit` before finding a solution.
+    TimeLimit,
+    /// Neither `solver.path` nor `SCIP_PATH` is set.
+    MissingPath,
+    /// The solver executable could not be started.
+    Spawn(PathBuf, io::Error),
+    /// The solver exited with an error.
+    Failed(String),
+    /// Writing the model or reading the output failed.
+    Io(io::Error),
+    /// The solver output is not valid UTF-8.
+    Encoding(FromUtf8Error),
+    /// The solver output contains something that is not a cell assignment.
+    Parse(String),
+    /// The solver thread stopped without sending a result.
+    Died,
+    /// The solver returned something that does not solve the puzzle.
+    Invalid(VerifyError),
+}
+
+impl fmt::Display for SolverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolverError::Infeasible => write!(f, "infeasible"),
+            SolverError::TimeLimit => write!(f, "time limit reached"),
+            SolverError::MissingPath => write!(f, "SCIP_PATH not set"),
+            SolverError::Spawn(path, err) => {
+                write!(f, "failed to start {}: {}", path.display(), err)
+            }
+            SolverError::Failed(stderr) => write!(f, "solver failed: {}", stderr),
+            SolverError::Io(err) => write!(f, "{}", err),
+            SolverError::Encoding(err) => write!(f, "solver output is not UTF-8: {}", err),
+            SolverError::Parse(token) => write!(f, "unexpected solver output `{}`", token),
+            SolverError::Died => write!(f, "solver thread stopped unexpectedly"),
+            SolverError::Invalid(err) => write!(f, "solver returned an invalid solution: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for SolverError {}
+
+impl From<io::Error> for SolverError {
+    fn from(err: io::Error) -> Self {
+        SolverError::Io(err)
+    }
+}
+
+impl From<FromUtf8Error> for SolverError {
+    fn from(err: FromUtf8Error) -> Self {
+        SolverError::Encoding(err)
+    }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 impl SolverHandler {
-    pub fn new(puzzel: [[Option<u8>; 9]; 9]) -> Self {
+    /// Starts solving the puzzle in the background.
+    pub fn new(puzzel: Grid, config: SolverConfig) -> Self {
         let (sender, receiver) = mpsc::channel();
         let handler = {
             thread::spawn(move || {
                 let now = Instant::now();
-                let result = solve(&puzzel).expect("meow?");
+                // the model may have stopped listening, so send errors are ignored
+                let result = solve_with(&puzzel, &config, &mut |message| {
+                    let _ = sender.send(message);
+                });
 
-                sender.send(result.map(|sol| (now.elapsed(), sol))).expect("");
+                let _ = sender.send(SolverMessage::Done(result.map(|sol| (now.elapsed(), sol))));
             })
         };
-        return Self { receiver, handler };
+        Self { receiver, handler }
     }
 
-    pub fn try_get(&self) -> Result<Result<(Duration, [[Option<u8>; 9]; 9]), SolverError>> {
-        Ok(self.receiver.try_recv()?)
+    /// The next message from the solver, if there is one.
+    pub fn try_get(&self) -> Option<SolverMessage> {
+        match self.receiver.try_recv() {
+            Ok(message) => Some(message),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(SolverMessage::Done(Err(SolverError::Died))),
+        }
     }
 }
 
-fn solve(puzzel: &[[Option<u8>; 9]; 9]) -> Result<Result<[[Option<u8>; 9]; 9], SolverError>> {
-    let dir = tempdir()?;
-    let file_path = dir.path().join("model.lp");
-    let mut model_file = File::create(file_path.clone())?;
+/// Solves the puzzle with the configured backend.
+pub fn solve(puzzel: &Grid, config: &SolverConfig) -> Result<Grid, SolverError> {
+    solve_with(puzzel, config, &mut |_| {})
+}
 
-    writeln!(model_file, "Maximize\n\t0")?;
+/// Solves the puzzle with the configured backend, reporting every line the
+/// solver prints as it arrives and the statistics once the solver is done.
+///
+/// No backend is trusted: a solution that does not solve the puzzle is
+/// returned as [`SolverError::Invalid`].
+pub fn solve_with(
+    puzzel: &Grid,
+    config: &SolverConfig,
+    report: &mut dyn FnMut(SolverMessage),
+) -> Result<Grid, SolverError> {
+    let solution = match config.backend {
+        #[cfg(feature = "scip")]
+        Backend::Scip => scip(puzzel, config, report),
+        Backend::Native => native(puzzel, report),
+    }?;
+    verify(puzzel, &solution).map_err(SolverError::Invalid)?;
+    Ok(solution)
+}
 
-    writeln!(model_file, "Subject To")?;
-    // only one number can be assigned per cell
-    for i in 0..9 {
-        for j in 0..9 {
-            writeln!(
-                model_file,
-                "{} = 1",
-                (1..=9)
-                    .map(|k| format!("x{}{}{}", i, j, k))
-                    .collect::<Vec<_>>()
-                    .join(" + ")
-            )?;
-        }
-    }
+/// Solves the puzzle in process, without any output to report.
+fn native(puzzel: &Grid, report: &mut dyn FnMut(SolverMessage)) -> Result<Grid, SolverError> {
+    #[cfg(not(target_arch = "wasm32"))]
+    let start = Instant::now();
+    let outcome = native::search(puzzel, 1);
+    let status = match outcome.solution {
+        Some(_) => "solution found",
+        None => "infeasible",
+    };
+    report(SolverMessage::Stats(Stats {
+        #[cfg(not(target_arch = "wasm32"))]
+        solver_wall: start.elapsed(),
+        status: Some(status.to_string()),
+        nodes: Some(outcome.nodes),
+        ..Stats::default()
+    }));
+    outcome.solution.ok_or(SolverError::Infeasible)
+}
 
-    // each number is exactly once in a row
-    for i in 0..9 {
-        for k in 1..=9 {
-            writeln!(
-                model_file,
-                "{} = 1",
-                (0..9)
-                    .map(|j| format!("x{}{}{}", i, j, k))
-                    .collect::<Vec<_>>()
-                    .join(" + ")
-            )?;
-        }
+/// Solves the puzzle by writing it as an LP model and running SCIP on it.
+#[cfg(feature = "scip")]
+fn scip(
+    puzzel: &Grid,
+    config: &SolverConfig,
+    report: &mut dyn FnMut(SolverMessage),
+) -> Result<Grid, SolverError> {
+    let build_start = Instant::now();
+    // whatever follows from the givens alone is settled here, so SCIP only
+    // gets the cells and digits that are still open
+    let Some(presolved) = native::propagate(puzzel) else {
+        report(SolverMessage::Line(
+            "presolve: the givens contradict each other".to_string(),
+        ));
+        report(SolverMessage::Stats(Stats {
+            model_build: build_start.elapsed(),
+            status: Some("infeasible by presolve".to_string()),
+            ..Stats::default()
+        }));
+        return Err(SolverError::Infeasible);
+    };
+    if presolved.is_solved() {
+        report(SolverMessage::Line(
+            "presolve: solved by propagation".to_string(),
+        ));
+        report(SolverMessage::Stats(Stats {
+            model_build: build_start.elapsed(),
+            status: Some("solved by presolve".to_string()),
+            ..Stats::default()
+        }));
+        return Ok(presolved.grid);
     }
+    // after propagation no row of the model is down to a single variable
+    let model = LpModel::new(&presolved.candidates);
+    report(SolverMessage::Line(format!(
+        "presolve: {} cells fixed, {} variables and {} constraints left",
+        presolved
+            .grid
+            .iter()
+            .flatten()
+            .filter(|cell| cell.is_some())
+            .count(),
+        model.variables.len(),
+        model.constraints.len()
+    )));
 
-    // each number is exactly once in a column
-    for j in 0..9 {
-        for k in 1..=9 {
-            writeln!(
-                model_file,
-                "{} = 1",
-                (0..9)
-                    .map(|i| format!("x{}{}{}", i, j, k))
-                    .collect::<Vec<_>>()
-                    .join(" + ")
-            )?;
-        }
-    }
+    let dir = tempdir()?;
+    let file_path = dir.path().join("model.lp");
+    let mut model_file = File::create(file_path.clone())?;
 
-    // each 3x3 square must have all numbers
-    for i in 0..3 {
-        for j in 0..3 {
-            for k in 1..=9 {
-                writeln!(
-                    model_file,
-                    "{} = 1",
-                    (0..3)
-                        .flat_map(|x| (0..3).map(move |y| format!(
-                            "x{}{}{}",
-                            3 * i + x,
-                            3 * j + y,
-                            k
-                        )))
-                        .collect::<Vec<_>>()
-                        .join(" + ")
-                )?;
-            }
-        }
-    }
+    write!(model_file, "{}", model)?;
 
-    // puzzel constraint
-    for i in 0..9 {
-        for j in 0..9 {
-            if let Some(k) = puzzel[i][j] {
-                writeln!(model_file, "x{}{}{} = 1", i, j, k)?;
-            }
-        }
+    let executable = config.executable().ok_or(SolverError::MissingPath)?;
+    let mut command = Command::new(&executable);
+    let settings = config.settings();
+    if !settings.is_empty() {
+        let settings_path = dir.path().join("scip.set");
+        fs::write(&settings_path, settings)?;
+        command.arg("-s").arg(settings_path);
     }
+    let model_build = build_start.elapsed();
 
-    // binary var
-    writeln!(
-        model_file,
-        "BINARY\n\t{}",
-        (0..9)
-            .flat_map(
-                |i| (0..9).flat_map(move |j| (1..=9).map(move |k| format!("x{}{}{}", i, j, k)))
-            )
-            .collect::<Vec<_>>()
-            .join(" ")
-    )?;
-
-    writeln!(model_file, "END")?;
-
-    let scip_path = env::var("SCIP_PATH")?;
-    let output = Command::new(scip_path)
+    let solver_start = Instant::now();
+    let mut child = command
         .arg("-f")
         .arg(file_path)
-        .output()?;
-    if !output.status.success() {
-        println!("Failed: {}", String::from_utf8(output.stderr)?);
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| SolverError::Spawn(executable, err))?;
+
+    // drain stderr on the side so a chatty solver cannot block on a full pipe
+    let mut stderr = child.stderr.take().expect("stderr is piped");
+    let stderr = thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = stderr.read_to_end(&mut buf);
+        String::from_utf8_lossy(&buf).into_owned()
+    });
+
+    let mut output = String::new();
+    let stdout = child.stdout.take().expect("stdout is piped");
+    for line in BufReader::new(stdout).split(b'\n') {
+        let line = String::from_utf8(line?)?;
+        let line = line.trim_end_matches('\r');
+        report(SolverMessage::Line(line.to_string()));
+        output.push_str(line);
+        output.push('\n');
+    }
+
+    let status = child.wait()?;
+    let solver_wall = solver_start.elapsed();
+    let stderr = stderr.join().unwrap_or_default();
+    if !status.success() {
+        return Err(SolverError::Failed(format!(
+            "{}: {}",
+            status,
+            stderr.trim()
+        )));
     }
-    let output = String::from_utf8(output.stdout)?;
 
     drop(model_file);
     dir.close()?;
 
-    return parse_scip_output(output);
+    let parse_start = Instant::now();
+    let stats = Stats::parse(&output);
+    // the solution only covers the cells left open by presolve
+    let result = parse_output(&output).map(|mut grid| {
+        for (row, fixed) in grid.iter_mut().zip(&presolved.grid) {
+            for (cell, fixed) in row.iter_mut().zip(fixed) {
+                if fixed.is_some() {
+                    *cell = *fixed;
+                }
+            }
+        }
+        grid
+    });
+    report(SolverMessage::Stats(Stats {
+        model_build,
+        solver_wall,
+        parsing: parse_start.elapsed(),
+        ..stats
+    }));
+    result
 }
 
-fn parse_scip_output(output: String) -> Result<Result<[[Option<u8>; 9]; 9], SolverError>> {
+/// Reads the solution SCIP prints for an [`LpModel`], only holding the cells
+/// that had variables in it.
+///
+/// Without a solution, the `SCIP Status` tells a puzzle without one from a
+/// run that hit the time limit.
+pub fn parse_output(output: &str) -> Result<Grid, SolverError> {
     let re = Regex::new(r"([^=]+)============([^=]+)=============([^=]+)=================================([^x]*)(?<sol>[^=S]*)Statistics([\s\S]+)$").unwrap();
 
-    let sol = if let Some(cap) = re.captures(&output) {
-        cap.name("sol").unwrap().as_str()
-    } else {
-        return Ok(Err(SolverError::Infeasible));
-    };
+    let sol = re
+        .captures(output)
+        .map_or("", |cap| cap.name("sol").unwrap().as_str());
     if sol.is_empty() {
-        return Ok(Err(SolverError::Infeasible));
+        let status = Stats::parse(output).status.unwrap_or_default();
+        if status.contains("time limit reached") {
+            return Err(SolverError::TimeLimit);
+        }
+        return Err(SolverError::Infeasible);
     }
-    let elements: Vec<&str> = sol.trim().split_whitespace().collect();
-    let pos: Vec<_> = elements
-        .chunks(3)
-        .map(|c| {
-            (
-                c[0].chars().nth(1),
-                c[0].chars().nth(2),
-                c[0].chars().nth(3),
-            )
-        })
-        .collect();
+    let elements: Vec<&str> = sol.split_whitespace().collect();
 
     let mut solution = [[None; 9]; 9];
-    for (i, j, k) in pos.iter() {
-        let i: usize = i.unwrap().to_digit(10).unwrap() as usize;
-        let j: usize = j.unwrap().to_digit(10).unwrap() as usize;
-        let k: u8 = k.unwrap().to_digit(10).unwrap() as u8;
-        solution[i][j] = Some(k);
+    for c in elements.chunks(3) {
+        let digits: Vec<_> = c[0]
+            .chars()
+            .skip(1)
+            .filter_map(|c| c.to_digit(10))
+            .collect();
+        match (c[0].chars().next(), digits.as_slice()) {
+            (Some('x'), &[i @ 0..=8, j @ 0..=8, k @ 1..=9]) if c[0].len() == 4 => {
+                solution[i as usize][j as usize] = Some(k as u8);
+            }
+            _ => return Err(SolverError::Parse(c[0].to_string())),
+        }
     }
-    return Ok(Ok(solution));
+    Ok(solution)
 }
 
 #[cfg(test)]
@@ -557,11 +874,78 @@ Integrals          :      Total       Avg%
   primal-ref       :       0.00       0.00
   dual-ref         :       0.00       0.00"#;
 
-        let sol = parse_scip_output(output.to_string());
+        let sol = parse_output(output);
         println!("{:?}", sol);
     }
 
     #[test]
+    fn test_parse_time_limit() {
+        let output = r#"
+read problem </tmp/model.lp>
+============
+
+original problem has 729 variables (729 bin, 0 int, 0 impl, 0 cont) and 324 constraints
+
+solve problem
+=============
+
+SCIP Status        : solving was interrupted [time limit reached]
+Solving Time (sec) : 0.01
+Solving Nodes      : 1
+Primal Bound       : -1.00000000000000e+20 (0 solutions)
+Dual Bound         : +0.00000000000000e+00
+Gap                : infinite
+
+primal solution (original space):
+=================================
+
+no solution available
+
+Statistics
+==========
+
+SCIP Status        : solving was interrupted [time limit reached]
+Total Time         :       0.01
+"#;
+        let result = parse_output(output);
+        assert!(
+            matches!(result, Err(SolverError::TimeLimit)),
+            "{:?}",
+            result
+        );
+
+        let output = output.replace(
+            "solving was interrupted [time limit reached]",
+            "problem is solved [infeasible]",
+        );
+        let result = parse_output(&output);
+        assert!(
+            matches!(result, Err(SolverError::Infeasible)),
+            "{:?}",
+            result
+        );
+    }
+
+    #[test]
+    fn test_lp_model() {
+        let model = LpModel::new(&[[crate::logic::ALL; 9]; 9]);
+        assert_eq!(model.variables.len(), 729);
+        assert_eq!(model.constraints.len(), 324);
+        let text = model.to_string();
+        assert!(text.starts_with("Maximize\n\t0\nSubject To\nx001 + x002 + "));
+        assert!(text.ends_with("x887 x888 x889\nEND\n"));
+
+        let mut puzzel = [[None; 9]; 9];
+        puzzel[0][0] = Some(1);
+        let presolved = native::propagate(&puzzel).unwrap();
+        let model = LpModel::new(&presolved.candidates);
+        // the row, column and box of the given lose digit 1
+        assert_eq!(model.variables.len(), 729 - 9 - 20);
+        assert!(!model.variables.contains(&"x011".to_string()));
+    }
+
+    #[test]
+    #[cfg(feature = "scip")]
     fn test_solve() {
         let puzzel = vec![
             (0, 1, 3),
@@ -595,7 +979,7 @@ Integrals          :      Total       Avg%
         for (i, j, k) in puzzel.into_iter() {
             sukoku[i][j] = Some(k);
         }
-        let _ = solve(&sukoku).unwrap();
+        let _ = solve(&sukoku, &SolverConfig::default()).unwrap();
     }
 
     #[test]
@@ -605,12 +989,59 @@ Integrals          :      Total       Avg%
         for (i, j, k) in puzzel.into_iter() {
             sukoku[i][j] = Some(k);
         }
-        let _ = solve(&sukoku).unwrap();
+        let result = solve(&sukoku, &SolverConfig::default());
+        assert!(
+            matches!(result, Err(SolverError::Infeasible)),
+            "{:?}",
+            result
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "scip")]
+    fn test_missing_executable() {
+        let config = SolverConfig {
+            path: Some("/nonexistent/scip".into()),
+            ..SolverConfig::default()
+        };
+        let result = solve(&[[None; 9]; 9], &config);
+        assert!(
+            matches!(result, Err(SolverError::Spawn(_, _))),
+            "{:?}",
+            result
+        );
+    }
+
+    #[test]
+    fn test_native() {
+        let config = SolverConfig {
+            backend: Backend::Native,
+            ..SolverConfig::default()
+        };
+        let mut puzzel = [[None; 9]; 9];
+        puzzel[4][4] = Some(5);
+        let mut stats = None;
+        let solution = solve_with(&puzzel, &config, &mut |message| {
+            if let SolverMessage::Stats(s) = message {
+                stats = Some(s);
+            }
+        })
+        .unwrap();
+        assert_eq!(solution[4][4], Some(5));
+        assert!(stats.unwrap().nodes.is_some());
+
+        puzzel[4][5] = Some(5);
+        let result = solve(&puzzel, &config);
+        assert!(
+            matches!(result, Err(SolverError::Infeasible)),
+            "{:?}",
+            result
+        );
     }
 
     #[test]
     fn test_env() {
-        let scip_path = env::var("SCIP_PATH");
+        let scip_path = std::env::var("SCIP_PATH");
         println!("{:?}", scip_path);
     }
 }
@@ -0,0 +1,143 @@
+//! Where the time of a solve went, read from the SCIP output.
+
+use std::time::Duration;
+
+/// Size of a problem as SCIP reports it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProblemSize {
+    pub variables: u64,
+    pub constraints: u64,
+}
+
+/// Where the time of a solve went, and what the solver reported about it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Stats {
+    /// Writing the model file.
+    pub model_build: Duration,
+    /// From starting the solver process until it exited.
+    pub solver_wall: Duration,
+    /// Reading the solution and statistics out of the solver output.
+    pub parsing: Duration,
+    /// `SCIP Status`, e.g. `problem is solved [optimal solution found]`.
+    pub status: Option<String>,
+    /// Solver-reported times in seconds.
+    pub total_time: Option<f64>,
+    pub solving_time: Option<f64>,
+    pub presolving_time: Option<f64>,
+    pub reading_time: Option<f64>,
+    pub original: Option<ProblemSize>,
+    pub presolved: Option<ProblemSize>,
+    /// Branch and bound nodes.
+    pub nodes: Option<u64>,
+}
+
+impl Stats {
+    /// Reads the statistics block SCIP prints at the end of a run.
+    ///
+    /// The timings measured around the solver are left at zero.
+    pub fn parse(output: &str) -> Self {
+        let mut stats = Stats::default();
+        let Some(start) = output.find("\nStatistics\n") else {
+            return stats;
+        };
+
+        let mut section = "";
+        let mut original = (None, None);
+        let mut presolved = (None, None);
+        for line in output[start..].lines() {
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            let key = if line.starts_with(' ') {
+                key.trim()
+            } else {
+                section = key.trim();
+                ""
+            };
+            let seconds = || value.split_whitespace().next().and_then(|v| v.parse().ok());
+            let count = || value.split_whitespace().next().and_then(|v| v.parse().ok());
+            match (section, key) {
+                ("SCIP Status", "") => stats.status = Some(value.to_string()),
+                ("Total Time", "") => stats.total_time = seconds(),
+                ("Total Time", "solving") => stats.solving_time = seconds(),
+                ("Total Time", "presolving") => stats.presolving_time = seconds(),
+                ("Total Time", "reading") => stats.reading_time = seconds(),
+                ("Original Problem", "Variables") => original.0 = count(),
+                ("Original Problem", "Constraints") => original.1 = count(),
+                ("Presolved Problem", "Variables") => presolved.0 = count(),
+                ("Presolved Problem", "Constraints") => presolved.1 = count(),
+                ("B&B Tree", "nodes") => stats.nodes = count(),
+                _ => {}
+            }
+        }
+        let size = |(variables, constraints)| match (variables, constraints) {
+            (Some(variables), Some(constraints)) => Some(ProblemSize {
+                variables,
+                constraints,
+            }),
+            _ => None,
+        };
+        stats.original = size(original);
+        stats.presolved = size(presolved);
+        stats
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let output = r#"
+SCIP Status        : problem is solved [optimal solution found]
+Solving Time (sec) : 9.99
+
+Statistics
+==========
+
+SCIP Status        : problem is solved [optimal solution found]
+Total Time         :       0.12
+  solving          :       0.10
+  presolving       :       0.04 (included in solving)
+  reading          :       0.01
+  copying          :       0.00 (0 times copied the problem)
+Original Problem   :
+  Problem name     : model.lp
+  Variables        : 729 (729 binary, 0 integer, 0 implicit integer, 0 continuous)
+  Constraints      : 350 initial, 350 maximal
+Presolved Problem  :
+  Problem name     : t_model.lp
+  Variables        : 12 (12 binary, 0 integer, 0 implicit integer, 0 continuous)
+  Constraints      : 8 initial, 8 maximal
+B&B Tree           :
+  number of runs   :          1
+  nodes            :          3 (1 internal, 2 leaves)
+"#;
+        let stats = Stats::parse(output);
+        assert_eq!(
+            stats.status.as_deref(),
+            Some("problem is solved [optimal solution found]")
+        );
+        assert_eq!(stats.total_time, Some(0.12));
+        assert_eq!(stats.solving_time, Some(0.10));
+        assert_eq!(stats.presolving_time, Some(0.04));
+        assert_eq!(stats.reading_time, Some(0.01));
+        assert_eq!(
+            stats.original,
+            Some(ProblemSize {
+                variables: 729,
+                constraints: 350
+            })
+        );
+        assert_eq!(
+            stats.presolved,
+            Some(ProblemSize {
+                variables: 12,
+                constraints: 8
+            })
+        );
+        assert_eq!(stats.nodes, Some(3));
+    }
+}
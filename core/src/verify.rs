@@ -0,0 +1,192 @@
+//! Checks that a solution solves a puzzle, and that the givens of a puzzle
+//! don't clash.
+
+use std::fmt;
+
+use crate::{logic::Unit, Grid};
+
+/// Why a solution returned by a backend is not a solution of the puzzle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerifyError {
+    /// A cell was left empty.
+    Missing { row: usize, column: usize },
+    /// A cell holds something other than a digit from 1 to 9.
+    BadDigit {
+        row: usize,
+        column: usize,
+        digit: u8,
+    },
+    /// A given of the puzzle was replaced by another digit.
+    GivenOverwritten {
+        row: usize,
+        column: usize,
+        given: u8,
+        found: u8,
+    },
+    /// A digit appears more than once in a unit.
+    Duplicate { unit: Unit, digit: u8 },
+}
+
+/// Checks a solution on its own, without trusting the solver that found it:
+/// every cell is filled with a digit, the givens are kept and no unit holds
+/// a digit twice.
+pub fn verify(puzzel: &Grid, solution: &Grid) -> Result<(), VerifyError> {
+    for (row, (givens, cells)) in puzzel.iter().zip(solution).enumerate() {
+        for (column, (given, cell)) in givens.iter().zip(cells).enumerate() {
+            let digit = cell.ok_or(VerifyError::Missing { row, column })?;
+            if !(1..=9).contains(&digit) {
+                return Err(VerifyError::BadDigit { row, column, digit });
+            }
+            if let Some(given) = *given {
+                if given != digit {
+                    return Err(VerifyError::GivenOverwritten {
+                        row,
+                        column,
+                        given,
+                        found: digit,
+                    });
+                }
+            }
+        }
+    }
+    for unit in Unit::all() {
+        let mut seen = 0u16;
+        for (row, column) in unit.cells() {
+            let digit = solution[row][column].unwrap_or_default();
+            if seen & (1 << digit) != 0 {
+                return Err(VerifyError::Duplicate { unit, digit });
+            }
+            seen |= 1 << digit;
+        }
+    }
+    Ok(())
+}
+
+/// The units of a puzzle that hold a digit twice, with the first digit
+/// repeated in each.
+pub fn conflicts(grid: &Grid) -> Vec<VerifyError> {
+    Unit::all()
+        .filter_map(|unit| {
+            let mut seen = 0u16;
+            unit.cells()
+                .filter_map(|(row, column)| grid[row][column])
+                .find(|&digit| {
+                    let duplicate = seen & (1 << digit) != 0;
+                    seen |= 1 << digit;
+                    duplicate
+                })
+                .map(|digit| VerifyError::Duplicate { unit, digit })
+        })
+        .collect()
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            VerifyError::Missing { row, column } => {
+                write!(f, "R{}C{} is empty", row + 1, column + 1)
+            }
+            VerifyError::BadDigit { row, column, digit } => {
+                write!(
+                    f,
+                    "R{}C{} holds {}, not a digit",
+                    row + 1,
+                    column + 1,
+                    digit
+                )
+            }
+            VerifyError::GivenOverwritten {
+                row,
+                column,
+                given,
+                found,
+            } => write!(
+                f,
+                "R{}C{} is given as {} but {} was found",
+                row + 1,
+                column + 1,
+                given,
+                found
+            ),
+            VerifyError::Duplicate { unit, digit } => write!(f, "{} has {} twice", unit, digit),
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::format::parse_grid;
+
+    #[test]
+    fn test_verify() {
+        let puzzel = parse_grid(
+            "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79",
+        )
+        .unwrap();
+        let solution = parse_grid(
+            "534678912672195348198342567859761423426853791713924856961537284287419635345286179",
+        )
+        .unwrap();
+        assert_eq!(verify(&puzzel, &solution), Ok(()));
+
+        let mut truncated = solution;
+        truncated[8][7] = None;
+        truncated[8][8] = None;
+        assert_eq!(
+            verify(&puzzel, &truncated),
+            Err(VerifyError::Missing { row: 8, column: 7 })
+        );
+
+        let mut bad = solution;
+        bad[4][4] = Some(0);
+        assert_eq!(
+            verify(&puzzel, &bad),
+            Err(VerifyError::BadDigit {
+                row: 4,
+                column: 4,
+                digit: 0
+            })
+        );
+
+        // a valid grid, but not for these givens
+        let mut other = solution;
+        for row in other.iter_mut() {
+            row.rotate_left(3);
+        }
+        assert_eq!(
+            verify(&puzzel, &other).unwrap_err().to_string(),
+            "R1C1 is given as 5 but 6 was found"
+        );
+
+        let mut duplicate = solution;
+        duplicate[0][2] = Some(5);
+        assert_eq!(
+            verify(&[[None; 9]; 9], &duplicate),
+            Err(VerifyError::Duplicate {
+                unit: Unit::Row(0),
+                digit: 5
+            })
+        );
+
+        assert_eq!(conflicts(&puzzel), vec![]);
+        let mut clash = puzzel;
+        clash[0][2] = Some(5);
+        assert_eq!(
+            conflicts(&clash),
+            vec![
+                VerifyError::Duplicate {
+                    unit: Unit::Row(0),
+                    digit: 5
+                },
+                VerifyError::Duplicate {
+                    unit: Unit::Box(0),
+                    digit: 5
+                }
+            ]
+        );
+    }
+}
@@ -0,0 +1,320 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    fs::{self, File},
+    io::{self, BufWriter, Read, Write},
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use clap::ValueEnum;
+use color_eyre::{eyre::WrapErr, Result};
+use serde::Serialize;
+
+use sudoku_core::{
+    format::{format_grid, parse_grid, split_line},
+    native,
+    solver::{self, SolverConfig, SolverError, SolverMessage},
+    stats::Stats,
+};
+
+use crate::cli::BatchArgs;
+
+/// How the records are written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// One JSON object per line.
+    #[default]
+    Jsonl,
+    /// Comma separated values with a header row.
+    Csv,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Solved,
+    Infeasible,
+    /// The puzzle could not be read or the solver failed on it.
+    Error,
+}
+
+/// The outcome for one puzzle of the input.
+#[derive(Debug, Serialize)]
+pub struct Record {
+    /// Line of the input the puzzle was read from.
+    pub line: usize,
+    pub input: String,
+    pub status: Status,
+    pub solution: Option<String>,
+    /// Number of solutions, counted up to two.
+    pub solutions: Option<usize>,
+    pub error: Option<String>,
+    pub model_build_ms: f64,
+    pub solver_wall_ms: f64,
+    pub parsing_ms: f64,
+    pub total_ms: f64,
+}
+
+/// Where the records go, in the requested format.
+enum Sink<W: Write> {
+    Jsonl(W),
+    Csv(Box<csv::Writer<W>>),
+}
+
+impl<W: Write> Sink<W> {
+    fn new(format: Format, writer: W) -> Self {
+        match format {
+            Format::Jsonl => Sink::Jsonl(writer),
+            Format::Csv => Sink::Csv(Box::new(csv::Writer::from_writer(writer))),
+        }
+    }
+
+    fn write(&mut self, record: &Record) -> Result<()> {
+        match self {
+            Sink::Jsonl(writer) => {
+                serde_json::to_writer(&mut *writer, record)?;
+                writeln!(writer)?;
+            }
+            Sink::Csv(writer) => writer.serialize(record)?,
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        match self {
+            Sink::Jsonl(writer) => writer.flush()?,
+            Sink::Csv(writer) => writer.flush()?,
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Status::Solved => write!(f, "solved"),
+            Status::Infeasible => write!(f, "infeasible"),
+            Status::Error => write!(f, "error"),
+        }
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Solves one puzzle, turning every failure into an error record.
+fn solve(line: usize, input: &str, config: &SolverConfig) -> Record {
+    let start = Instant::now();
+    let mut record = Record {
+        line,
+        input: input.to_string(),
+        status: Status::Error,
+        solution: None,
+        solutions: None,
+        error: None,
+        model_build_ms: 0.0,
+        solver_wall_ms: 0.0,
+        parsing_ms: 0.0,
+        total_ms: 0.0,
+    };
+    match parse_grid(input) {
+        Ok(grid) => {
+            record.solutions = Some(native::count(&grid, 2));
+            let mut stats = Stats::default();
+            let result = solver::solve_with(&grid, config, &mut |message| {
+                if let SolverMessage::Stats(s) = message {
+                    stats = s;
+                }
+            });
+            match result {
+                Ok(solution) => {
+                    record.status = Status::Solved;
+                    record.solution = Some(format_grid(&solution));
+                }
+                Err(SolverError::Infeasible) => record.status = Status::Infeasible,
+                Err(err) => record.error = Some(err.to_string()),
+            }
+            record.model_build_ms = millis(stats.model_build);
+            record.solver_wall_ms = millis(stats.solver_wall);
+            record.parsing_ms = millis(stats.parsing);
+        }
+        Err(err) => record.error = Some(err.to_string()),
+    }
+    record.total_ms = millis(start.elapsed());
+    record
+}
+
+/// Reads a file of puzzles, or stdin if there is no path or it is `-`.
+pub fn read_input(path: Option<&Path>) -> Result<String> {
+    match path {
+        Some(path) if path.as_os_str() != "-" => {
+            fs::read_to_string(path).wrap_err_with(|| format!("failed to read {}", path.display()))
+        }
+        _ => {
+            let mut input = String::new();
+            io::stdin().read_to_string(&mut input)?;
+            Ok(input)
+        }
+    }
+}
+
+/// Solves every puzzle of the input on a pool of worker threads.
+///
+/// Records are written in input order as soon as all earlier ones are done.
+pub fn run(args: &BatchArgs, config: &SolverConfig) -> Result<()> {
+    let input = read_input(args.input.as_deref())?;
+    let puzzles: Vec<(usize, &str)> = input
+        .lines()
+        .enumerate()
+        .filter_map(|(n, line)| split_line(line).map(|(puzzle, _)| (n + 1, puzzle)))
+        .collect();
+    let writer: Box<dyn Write> = match &args.output {
+        Some(path) => Box::new(
+            File::create(path).wrap_err_with(|| format!("failed to create {}", path.display()))?,
+        ),
+        None => Box::new(io::stdout().lock()),
+    };
+    let mut sink = Sink::new(args.format, BufWriter::new(writer));
+    let jobs = args
+        .jobs
+        .or_else(|| thread::available_parallelism().ok())
+        .map_or(1, usize::from);
+
+    let counts = solve_all(&puzzles, jobs, config, &mut sink)?;
+
+    let summary: Vec<String> = counts
+        .iter()
+        .map(|(status, count)| format!("{} {}", count, status))
+        .collect();
+    eprintln!("{} puzzles: {}", puzzles.len(), summary.join(", "));
+    Ok(())
+}
+
+/// Solves the puzzles, given with their line numbers, on `jobs` threads and
+/// writes their records in order, returning how many ended how.
+fn solve_all<W: Write>(
+    puzzles: &[(usize, &str)],
+    jobs: usize,
+    config: &SolverConfig,
+    sink: &mut Sink<W>,
+) -> Result<BTreeMap<Status, usize>> {
+    let next = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+    let mut counts = BTreeMap::new();
+    thread::scope(|scope| -> Result<()> {
+        for _ in 0..jobs.min(puzzles.len()) {
+            let (sender, next) = (sender.clone(), &next);
+            scope.spawn(move || loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some((line, puzzle)) = puzzles.get(index) else {
+                    break;
+                };
+                // the writer is gone after an output error
+                if sender.send((index, solve(*line, puzzle, config))).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(sender);
+
+        let mut pending = BTreeMap::new();
+        let mut written = 0;
+        for (index, record) in receiver {
+            pending.insert(index, record);
+            while let Some(record) = pending.remove(&written) {
+                sink.write(&record)?;
+                *counts.entry(record.status).or_insert(0) += 1;
+                written += 1;
+            }
+        }
+        sink.flush()
+    })?;
+    Ok(counts)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_invalid_input() {
+        let mut record = solve(3, "12x", &SolverConfig::default());
+        assert_eq!(record.line, 3);
+        assert_eq!(record.status, Status::Error);
+        assert_eq!(record.error.as_deref(), Some("expected 81 cells, found 3"));
+
+        // the time it took to fail varies
+        record.total_ms = 0.5;
+        let mut sink = Sink::new(Format::Csv, Vec::new());
+        sink.write(&record).unwrap();
+        let Sink::Csv(writer) = sink else {
+            unreachable!()
+        };
+        let csv = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+        assert_eq!(
+            csv.lines().next(),
+            Some("line,input,status,solution,solutions,error,model_build_ms,solver_wall_ms,parsing_ms,total_ms")
+        );
+        assert_eq!(
+            csv.lines().nth(1),
+            Some("3,12x,error,,,\"expected 81 cells, found 3\",0.0,0.0,0.0,0.5")
+        );
+    }
+
+    #[test]
+    fn test_order() {
+        let solved =
+            "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+        let infeasible = format!("55{}", &solved[2..]);
+        let puzzles: Vec<(usize, &str)> =
+            [solved, "12x", &infeasible, solved, "hello", &infeasible]
+                .into_iter()
+                .enumerate()
+                .map(|(n, puzzle)| (n + 1, puzzle))
+                .collect();
+        let config = SolverConfig {
+            backend: solver::Backend::Native,
+            ..SolverConfig::default()
+        };
+        let mut sink = Sink::new(Format::Jsonl, Vec::new());
+        let counts = solve_all(&puzzles, 4, &config, &mut sink).unwrap();
+        assert_eq!(
+            counts,
+            BTreeMap::from([
+                (Status::Solved, 2),
+                (Status::Infeasible, 2),
+                (Status::Error, 2)
+            ])
+        );
+
+        let Sink::Jsonl(output) = sink else {
+            unreachable!()
+        };
+        let records: Vec<serde_json::Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let lines: Vec<_> = records.iter().map(|record| &record["line"]).collect();
+        assert_eq!(lines, [1, 2, 3, 4, 5, 6]);
+        let statuses: Vec<_> = records.iter().map(|record| &record["status"]).collect();
+        assert_eq!(
+            statuses,
+            [
+                "solved",
+                "error",
+                "infeasible",
+                "solved",
+                "error",
+                "infeasible"
+            ]
+        );
+    }
+}
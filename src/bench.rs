@@ -0,0 +1,168 @@
+use std::time::{Duration, Instant};
+
+use color_eyre::{eyre::bail, Result};
+
+use sudoku_core::{
+    format::{parse_grid, split_line},
+    solver::{self, Backend, SolverConfig, SolverError},
+    Grid,
+};
+
+use crate::{batch::read_input, cli::BenchArgs};
+
+/// How one backend did on the corpus.
+#[derive(Debug, Default)]
+struct Run {
+    solved: usize,
+    infeasible: usize,
+    failed: usize,
+    /// Time spent on the puzzles the solver got an answer for.
+    elapsed: Duration,
+    /// Whether each puzzle was solved, `None` where the solver failed.
+    results: Vec<Option<bool>>,
+    /// Why the solver failed the first time it did.
+    error: Option<SolverError>,
+}
+
+impl Run {
+    fn puzzles(&self) -> usize {
+        self.results.len()
+    }
+
+    /// Answers per second, leaving out the puzzles the solver failed on.
+    fn rate(&self) -> f64 {
+        (self.solved + self.infeasible) as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+
+    /// Fails with the solver error if the backend failed on every puzzle,
+    /// which leaves nothing to compare.
+    fn check(self, name: &str) -> Result<Self> {
+        match self.error {
+            Some(err) if self.failed == self.puzzles() => {
+                bail!("{} failed on every puzzle: {}", name, err)
+            }
+            _ => Ok(self),
+        }
+    }
+
+    fn report(&self, name: &str) -> String {
+        format!(
+            "{:<7}{:>7} puzzles in {:>9.1?} {:>12.1} puzzles/s  ({} solved, {} infeasible, {} failed)",
+            name,
+            self.puzzles(),
+            self.elapsed,
+            self.rate(),
+            self.solved,
+            self.infeasible,
+            self.failed
+        )
+    }
+}
+
+/// Solves the puzzles one after the other with a backend.
+fn measure(grids: &[Grid], config: &SolverConfig) -> Run {
+    let mut run = Run::default();
+    for grid in grids {
+        let start = Instant::now();
+        let result = match solver::solve_with(grid, config, &mut |_| {}) {
+            Ok(_) => {
+                run.solved += 1;
+                Some(true)
+            }
+            Err(SolverError::Infeasible) => {
+                run.infeasible += 1;
+                Some(false)
+            }
+            Err(err) => {
+                run.failed += 1;
+                run.error.get_or_insert(err);
+                None
+            }
+        };
+        if result.is_some() {
+            run.elapsed += start.elapsed();
+        }
+        run.results.push(result);
+    }
+    run
+}
+
+/// Compares the native solver to SCIP on a corpus, in puzzles per second.
+///
+/// SCIP only gets a sample of the corpus, since it is far slower.
+pub fn run(args: &BenchArgs, config: &SolverConfig) -> Result<()> {
+    let input = read_input(args.input.as_deref())?;
+    let mut skipped = 0;
+    let grids: Vec<Grid> = input
+        .lines()
+        .filter_map(split_line)
+        .filter_map(|(puzzle, _)| {
+            let grid = parse_grid(puzzle).ok();
+            skipped += grid.is_none() as usize;
+            grid
+        })
+        .collect();
+    if skipped > 0 {
+        eprintln!("skipped {} lines that are not puzzles", skipped);
+    }
+
+    let native = measure(
+        &grids,
+        &SolverConfig {
+            backend: Backend::Native,
+            ..config.clone()
+        },
+    )
+    .check("native")?;
+    println!("{}", native.report("native"));
+    let sample = &grids[..args.scip_sample.min(grids.len())];
+    if sample.is_empty() {
+        return Ok(());
+    }
+
+    let scip = measure(
+        sample,
+        &SolverConfig {
+            backend: Backend::Scip,
+            ..config.clone()
+        },
+    )
+    .check("scip")?;
+    println!("{}", scip.report("scip"));
+    println!("native is {:.0}x faster", native.rate() / scip.rate());
+    let disagreements = sample
+        .iter()
+        .zip(native.results.iter().zip(&scip.results))
+        .filter(|(_, (native, scip))| native.is_some() && scip.is_some() && native != scip)
+        .count();
+    if disagreements > 0 {
+        println!(
+            "{} puzzles of the sample are solved by one backend and infeasible for the other",
+            disagreements
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_failing_backend() {
+        let config = SolverConfig {
+            backend: Backend::Scip,
+            path: Some("/nonexistent/scip".into()),
+            ..SolverConfig::default()
+        };
+        let run = measure(&[[[None; 9]; 9]; 2], &config);
+        assert_eq!((run.failed, run.rate()), (2, 0.0));
+        let err = run.check("scip").unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("scip failed on every puzzle: failed to start /nonexistent/scip"),
+            "{}",
+            err
+        );
+    }
+}
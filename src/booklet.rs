@@ -0,0 +1,226 @@
+use std::fs;
+
+use color_eyre::{
+    eyre::{bail, WrapErr},
+    Result,
+};
+
+use sudoku_core::{
+    format::{parse_collection, Entry},
+    generate::{self, Difficulty},
+    native,
+    solver::{self, SolverConfig},
+    Grid,
+};
+
+use crate::{
+    batch::read_input,
+    cli::BookletArgs,
+    export::{self, Shape, PAGE},
+};
+
+const MARGIN: f64 = 50.0;
+/// Answer grids on a page, as columns and rows.
+const ANSWERS: (usize, usize) = (4, 5);
+
+/// A puzzle of the booklet, checked and solved.
+struct Puzzle {
+    entry: Entry,
+    solution: Grid,
+    difficulty: Difficulty,
+}
+
+/// One grid of a page, with the lines above it.
+struct Slot<'a> {
+    caption: String,
+    meta: &'a str,
+    puzzel: &'a Grid,
+    solution: Option<&'a Grid>,
+}
+
+/// Lays the slots out `columns` by `rows` to a page, under a heading.
+fn pages(heading: &str, slots: &[Slot], (columns, rows): (usize, usize)) -> Vec<Vec<Shape>> {
+    let (top, bottom) = (85.0, PAGE.1 - 40.0);
+    let slot_width = (PAGE.0 - 2.0 * MARGIN) / columns as f64;
+    let slot_height = (bottom - top) / rows as f64;
+    let caption_size = (0.07 * slot_height).clamp(8.0, 12.0);
+    let meta_size = 0.8 * caption_size;
+    slots
+        .chunks(columns * rows)
+        .enumerate()
+        .map(|(page, slots)| {
+            let mut shapes = vec![Shape::Text {
+                at: (MARGIN, 60.0),
+                size: 16.0,
+                text: format!("{}, page {}", heading, page + 1),
+                bold: true,
+                gray: 0.0,
+            }];
+            for (n, slot) in slots.iter().enumerate() {
+                let left = MARGIN + (n % columns) as f64 * slot_width;
+                let slot_top = top + (n / columns) as f64 * slot_height;
+                let mut grid_top = slot_top + 1.3 * caption_size;
+                if !slot.meta.is_empty() {
+                    grid_top += 1.3 * meta_size;
+                }
+                let size = (slot_width - 16.0).min(slot_top + slot_height - grid_top - 12.0);
+                let grid_left = left + (slot_width - size) / 2.0;
+                shapes.push(Shape::Text {
+                    at: (grid_left, slot_top + caption_size),
+                    size: caption_size,
+                    text: slot.caption.clone(),
+                    bold: true,
+                    gray: 0.0,
+                });
+                if !slot.meta.is_empty() {
+                    shapes.push(Shape::Text {
+                        at: (grid_left, slot_top + 1.3 * caption_size + meta_size),
+                        size: meta_size,
+                        text: slot.meta.to_string(),
+                        bold: false,
+                        gray: 0.0,
+                    });
+                }
+                export::draw_grid(
+                    &mut shapes,
+                    (grid_left, grid_top + 4.0),
+                    size / 9.0,
+                    slot.puzzel,
+                    slot.solution,
+                );
+            }
+            shapes
+        })
+        .collect()
+}
+
+/// Fails unless the puzzle has exactly one solution, then solves it with
+/// the configured backend.
+fn check(number: usize, entry: Entry, config: &SolverConfig) -> Result<Puzzle> {
+    match native::count(&entry.grid, 2) {
+        0 => bail!("puzzle {} has no solution", number),
+        1 => {}
+        _ => bail!("puzzle {} has more than one solution", number),
+    }
+    let solution = solver::solve_with(&entry.grid, config, &mut |_| {})
+        .wrap_err_with(|| format!("failed to solve puzzle {}", number))?;
+    Ok(Puzzle {
+        difficulty: generate::rate(&entry.grid),
+        entry,
+        solution,
+    })
+}
+
+/// Writes a PDF with the puzzles several to a page, followed by their
+/// solutions as an answer key.
+pub fn run(args: &BookletArgs, config: &SolverConfig) -> Result<()> {
+    let entries = match args.generate {
+        Some(count) => (0..count.get())
+            .map(|_| Entry {
+                grid: generate::generate(),
+                meta: String::new(),
+            })
+            .collect(),
+        None => parse_collection(&read_input(args.input.as_deref())?)?,
+    };
+    if entries.is_empty() {
+        bail!("no puzzles to put in the booklet");
+    }
+    let puzzles = entries
+        .into_iter()
+        .enumerate()
+        .map(|(n, entry)| check(n + 1, entry, config))
+        .collect::<Result<Vec<_>>>()?;
+
+    let per_page = args.per_page as usize;
+    let columns = if per_page > 2 { 2 } else { 1 };
+    let questions: Vec<Slot> = puzzles
+        .iter()
+        .enumerate()
+        .map(|(n, puzzle)| Slot {
+            caption: format!("{}. {}", n + 1, puzzle.difficulty),
+            meta: &puzzle.entry.meta,
+            puzzel: &puzzle.entry.grid,
+            solution: None,
+        })
+        .collect();
+    let answers: Vec<Slot> = puzzles
+        .iter()
+        .enumerate()
+        .map(|(n, puzzle)| Slot {
+            caption: (n + 1).to_string(),
+            meta: "",
+            puzzel: &puzzle.entry.grid,
+            solution: Some(&puzzle.solution),
+        })
+        .collect();
+    let mut document = pages(
+        &args.title,
+        &questions,
+        (columns, per_page.div_ceil(columns)),
+    );
+    document.extend(pages(
+        &format!("{}: answers", args.title),
+        &answers,
+        ANSWERS,
+    ));
+    fs::write(&args.output, export::pdf_document(&document))
+        .wrap_err_with(|| format!("failed to write {}", args.output.display()))?;
+    eprintln!("{} puzzles on {} pages", puzzles.len(), document.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use sudoku_core::solver::Backend;
+
+    #[test]
+    fn test_booklet() {
+        let config = SolverConfig {
+            backend: Backend::Native,
+            ..SolverConfig::default()
+        };
+        let mut grid = [[None; 9]; 9];
+        grid[0][0] = Some(1);
+        let entry = |grid| Entry {
+            grid,
+            meta: String::new(),
+        };
+        assert_eq!(
+            check(3, entry(grid), &config).err().unwrap().to_string(),
+            "puzzle 3 has more than one solution"
+        );
+        grid[0][1] = Some(1);
+        assert_eq!(
+            check(4, entry(grid), &config).err().unwrap().to_string(),
+            "puzzle 4 has no solution"
+        );
+
+        let puzzle = check(1, entry(generate::generate()), &config).unwrap();
+        let slots: Vec<Slot> = (0..7)
+            .map(|n| Slot {
+                caption: n.to_string(),
+                meta: "",
+                puzzel: &puzzle.entry.grid,
+                solution: Some(&puzzle.solution),
+            })
+            .collect();
+        let pages = pages("Test", &slots, (2, 2));
+        assert_eq!(pages.len(), 2);
+        // every digit is drawn, and inside the page
+        let digits = pages[1]
+            .iter()
+            .filter(|shape| matches!(shape, Shape::Text { text, .. } if text.len() == 1))
+            .count();
+        assert_eq!(digits, 3 * 81 + 3);
+        for shape in pages.iter().flatten() {
+            if let Shape::Line { from, to, .. } = shape {
+                for (x, y) in [from, to] {
+                    assert!((0.0..=PAGE.0).contains(x) && (0.0..=PAGE.1).contains(y));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,187 @@
+use std::{num::NonZeroUsize, path::PathBuf};
+
+use clap::{Args, Parser, Subcommand};
+
+use crate::{batch::Format, fpuzzles::Site, transform::Transform};
+
+/// A TUI sudoku solver.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Cli {
+    /// Start with an empty board instead of resuming the saved session
+    #[arg(long)]
+    pub new: bool,
+
+    /// Config file to use instead of `$XDG_CONFIG_HOME/sudoku/config.toml`
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Puzzle collection to work through, one puzzle per line
+    #[arg(long, value_name = "FILE")]
+    pub collection: Option<PathBuf>,
+
+    /// Screenshot or photo of a puzzle to check and correct on the board
+    #[arg(long, value_name = "FILE")]
+    pub image: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Solve every puzzle of a file without the TUI, one record per puzzle
+    Batch(BatchArgs),
+    /// Compare the native solver to SCIP on a corpus, in puzzles per second
+    Bench(BenchArgs),
+    /// Drop puzzles that are the same as an earlier one up to symmetry
+    Dedup(DedupArgs),
+    /// Look a puzzle up in the library, up to symmetry
+    Lookup(LookupArgs),
+    /// Transform every puzzle of a collection, to disguise reused grids
+    Transform(TransformArgs),
+    /// Draw a puzzle as a printable SVG or PDF page
+    Export(ExportArgs),
+    /// Lay out puzzles several to a page with an answer key, as one PDF
+    Booklet(BookletArgs),
+    /// Read puzzles from f-puzzles JSON or f-puzzles and SudokuPad links
+    Import(ImportArgs),
+    /// Print an f-puzzles or SudokuPad link for every puzzle of a collection
+    Link(LinkArgs),
+    /// Read a puzzle from a PNG or JPEG screenshot or photo
+    Scan(ScanArgs),
+    /// Answer solve, count, validate, rate and generate requests over HTTP
+    Serve(ServeArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct BatchArgs {
+    /// Puzzles, one per line; reads stdin if missing or `-`
+    pub input: Option<PathBuf>,
+
+    /// Number of puzzles solved at the same time [default: number of CPUs]
+    #[arg(short, long)]
+    pub jobs: Option<NonZeroUsize>,
+
+    /// Format of the records
+    #[arg(short, long, value_enum, default_value_t)]
+    pub format: Format,
+
+    /// Write the records to FILE instead of stdout
+    #[arg(short, long, value_name = "FILE")]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct BenchArgs {
+    /// Puzzles, one per line; reads stdin if missing or `-`
+    pub input: Option<PathBuf>,
+
+    /// Number of puzzles to also run through SCIP, 0 to skip it
+    #[arg(long, value_name = "N", default_value_t = 100)]
+    pub scip_sample: usize,
+}
+
+#[derive(Debug, Args)]
+pub struct DedupArgs {
+    /// Puzzles, one per line; reads stdin if missing or `-`
+    pub input: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct LookupArgs {
+    /// The puzzle as 81 characters, with `.` or `0` for empty cells
+    pub puzzle: String,
+
+    /// Collection to search instead of the `library` of the config file
+    #[arg(long, value_name = "FILE")]
+    pub library: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct TransformArgs {
+    /// Puzzles, one per line; reads stdin if missing or `-`
+    pub input: Option<PathBuf>,
+
+    /// Applied in order: rotate90, rotate180, rotate270, mirror, transpose,
+    /// swap-rows=R,R, swap-bands=B,B, swap-stacks=S,S or relabel=DIGITS
+    #[arg(short, long = "transform", value_name = "TRANSFORM")]
+    pub transforms: Vec<Transform>,
+
+    /// Relabel the digits of every puzzle in a different random order
+    #[arg(long)]
+    pub shuffle: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct ExportArgs {
+    /// Puzzles, one per line; reads stdin if missing or `-`
+    pub input: Option<PathBuf>,
+
+    /// File to write, `.svg` or `.pdf`
+    #[arg(short, long, value_name = "FILE")]
+    pub output: PathBuf,
+
+    /// Which puzzle of the input to draw, counting from 1
+    #[arg(short, long, value_name = "N", default_value_t = NonZeroUsize::MIN)]
+    pub puzzle: NonZeroUsize,
+
+    /// Fill in the solution in a lighter color
+    #[arg(long)]
+    pub solution: bool,
+
+    /// Heading of the page, `Puzzle N` by default
+    #[arg(long)]
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct BookletArgs {
+    /// Puzzles, one per line; reads stdin if missing or `-`
+    pub input: Option<PathBuf>,
+
+    /// Generate this many puzzles instead of reading them
+    #[arg(short, long, value_name = "N", conflicts_with = "input")]
+    pub generate: Option<NonZeroUsize>,
+
+    /// PDF file to write
+    #[arg(short, long, value_name = "FILE")]
+    pub output: PathBuf,
+
+    /// Puzzles on a page
+    #[arg(long, value_name = "N", default_value_t = 4, value_parser = clap::value_parser!(u8).range(1..=6))]
+    pub per_page: u8,
+
+    /// Heading of the pages
+    #[arg(long, default_value = "Sudoku")]
+    pub title: String,
+}
+
+#[derive(Debug, Args)]
+pub struct ImportArgs {
+    /// f-puzzles JSON, or links one per line; reads stdin if missing or `-`
+    pub input: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct LinkArgs {
+    /// Puzzles, one per line; reads stdin if missing or `-`
+    pub input: Option<PathBuf>,
+
+    /// Tool the links open in
+    #[arg(long, value_enum, default_value_t)]
+    pub site: Site,
+}
+
+#[derive(Debug, Args)]
+pub struct ScanArgs {
+    /// PNG or JPEG file
+    pub image: PathBuf,
+}
+
+#[derive(Debug, Args)]
+pub struct ServeArgs {
+    /// Address to listen on instead of the `[serve]` one of the config file
+    #[arg(long, value_name = "HOST:PORT")]
+    pub address: Option<String>,
+}
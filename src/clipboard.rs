@@ -0,0 +1,151 @@
+use std::{
+    env, fs,
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use color_eyre::{eyre::WrapErr, Result};
+use serde::Deserialize;
+
+use sudoku_core::{
+    format::{format_grid, format_table},
+    Grid,
+};
+
+/// How a grid is written when copied.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    /// 81 characters on one line, `.` for empty cells.
+    #[default]
+    Line,
+    /// Nine rows with `|` and `-` between the boxes.
+    Grid,
+    /// Nine rows of nine characters, as in `.sdk` files.
+    Sdk,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClipboardConfig {
+    pub format: Format,
+    /// Copy with the OSC 52 escape; when off, or the terminal is known not
+    /// to support it, the text is written to `file` instead.
+    pub osc52: bool,
+    /// Defaults to `$XDG_DATA_HOME/sudoku/clipboard.txt`.
+    pub file: Option<PathBuf>,
+}
+
+/// Where copied text ended up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Copied {
+    Clipboard,
+    File(PathBuf),
+}
+
+impl Default for ClipboardConfig {
+    fn default() -> Self {
+        Self {
+            format: Format::Line,
+            osc52: true,
+            file: None,
+        }
+    }
+}
+
+impl Format {
+    pub fn grid(self, grid: &Grid) -> String {
+        match self {
+            Format::Line => format_grid(grid),
+            Format::Grid => format_table(grid),
+            Format::Sdk => format_grid(grid)
+                .as_bytes()
+                .chunks(9)
+                .map(|row| String::from_utf8_lossy(row).into_owned())
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// The escape that asks the terminal to put text on the system clipboard.
+fn osc52(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", base64(text.as_bytes()))
+}
+
+/// Whether the terminal can be expected to understand OSC 52; the Linux
+/// console and dumb terminals silently drop it.
+fn supports_osc52() -> bool {
+    env::var("TERM").is_ok_and(|term| !matches!(term.as_str(), "" | "dumb" | "linux"))
+}
+
+/// Puts text on the clipboard, or in the fallback file.
+pub fn copy(text: &str, config: &ClipboardConfig) -> Result<Copied> {
+    if config.osc52 && supports_osc52() {
+        // the terminal is drawn on stderr
+        let mut stderr = io::stderr();
+        if stderr
+            .write_all(osc52(text).as_bytes())
+            .and_then(|_| stderr.flush())
+            .is_ok()
+        {
+            return Ok(Copied::Clipboard);
+        }
+    }
+    let path = config
+        .file
+        .clone()
+        .or_else(|| dirs::data_dir().map(|dir| dir.join("sudoku").join("clipboard.txt")))
+        .unwrap_or_else(|| PathBuf::from("clipboard.txt"));
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, format!("{}\n", text))
+        .wrap_err_with(|| format!("failed to write {}", path.display()))?;
+    Ok(Copied::File(path))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use sudoku_core::format::{parse_grid, parse_puzzle};
+
+    #[test]
+    fn test_copy_format() {
+        assert_eq!(base64(b"sudoku"), "c3Vkb2t1");
+        assert_eq!(base64(b"sudok"), "c3Vkb2s=");
+        assert_eq!(base64(b"sudo"), "c3Vkbw==");
+        assert_eq!(osc52("hi"), "\x1b]52;c;aGk=\x07");
+
+        let line =
+            "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+        let grid = parse_grid(line).unwrap();
+        assert_eq!(Format::Line.grid(&grid), line);
+        let sdk = Format::Sdk.grid(&grid);
+        assert_eq!(sdk.lines().next(), Some("53..7...."));
+        assert_eq!(sdk.lines().count(), 9);
+        let table = Format::Grid.grid(&grid);
+        assert_eq!(table.lines().next(), Some(" 5 3 . | . 7 . | . . ."));
+        assert_eq!(parse_puzzle(&table).unwrap(), grid);
+    }
+}
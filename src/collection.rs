@@ -0,0 +1,94 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use color_eyre::{
+    eyre::{eyre, WrapErr},
+    Result,
+};
+use serde::{Deserialize, Serialize};
+use sudoku_core::{
+    canon,
+    format::{parse_collection, Entry},
+};
+
+/// How far a puzzle of the collection has been worked through.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Status {
+    #[default]
+    Unsolved,
+    /// Solved by the solver or by hand, with the time it took.
+    Solved(Duration),
+    Infeasible,
+}
+
+/// A file of puzzles, one per line.
+#[derive(Debug)]
+pub struct Collection {
+    pub path: PathBuf,
+    pub entries: Vec<Entry>,
+    pub status: Vec<Status>,
+    /// For each entry, the first earlier entry that is the same puzzle.
+    pub duplicates: Vec<Option<usize>>,
+    /// Entry last opened into the board.
+    pub current: Option<usize>,
+    /// Entry highlighted in the list.
+    pub selected: usize,
+}
+
+/// What is saved of a collection between sittings.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CollectionState {
+    pub path: PathBuf,
+    pub current: Option<usize>,
+    pub status: Vec<Status>,
+}
+
+impl Collection {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .wrap_err_with(|| format!("failed to read puzzle collection {}", path.display()))?;
+        let entries = parse_collection(&content)
+            .wrap_err_with(|| format!("invalid puzzle collection {}", path.display()))?;
+        if entries.is_empty() {
+            return Err(eyre!("no puzzles in {}", path.display()));
+        }
+        Ok(Collection {
+            // so the saved progress is found again from another directory
+            path: fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf()),
+            status: vec![Status::Unsolved; entries.len()],
+            duplicates: canon::duplicates(&entries.iter().map(|e| e.grid).collect::<Vec<_>>()),
+            entries,
+            current: None,
+            selected: 0,
+        })
+    }
+
+    /// Takes over the progress of an earlier sitting on the same file.
+    ///
+    /// It is dropped if the number of puzzles changed in the meantime.
+    pub fn restore(&mut self, state: CollectionState) {
+        if state.path != self.path || state.status.len() != self.entries.len() {
+            return;
+        }
+        self.status = state.status;
+        self.current = state.current.filter(|&index| index < self.entries.len());
+        self.selected = self.current.unwrap_or_default();
+    }
+
+    pub fn state(&self) -> CollectionState {
+        CollectionState {
+            path: self.path.clone(),
+            current: self.current,
+            status: self.status.clone(),
+        }
+    }
+
+    /// Moves the list selection, staying within the collection.
+    pub fn select(&mut self, offset: isize) {
+        let last = self.entries.len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(offset).min(last);
+    }
+}
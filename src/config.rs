@@ -0,0 +1,235 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use color_eyre::{
+    eyre::{bail, WrapErr},
+    Result,
+};
+use ratatui::style::Color;
+use serde::{Deserialize, Deserializer};
+use sudoku_core::solver::SolverConfig;
+
+use crate::{clipboard::ClipboardConfig, export::ExportConfig, keymap::Keymap, serve::ServeConfig};
+
+/// User configuration, read from `$XDG_CONFIG_HOME/sudoku/config.toml`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Interval between two ticks in milliseconds.
+    pub tick_rate: u64,
+    /// Show A-I / 1-9 labels next to the rows and columns.
+    pub labels: bool,
+    /// Puzzle collection that `lookup` searches unless given another.
+    pub library: Option<PathBuf>,
+    pub solver: SolverConfig,
+    pub clipboard: ClipboardConfig,
+    pub export: ExportConfig,
+    pub serve: ServeConfig,
+    pub keys: Keymap,
+    pub theme: Theme,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    #[serde(deserialize_with = "color")]
+    pub cursor_fg: Color,
+    #[serde(deserialize_with = "color")]
+    pub cursor_bg: Color,
+    #[serde(deserialize_with = "color")]
+    pub solved_fg: Color,
+    #[serde(deserialize_with = "color")]
+    pub solved_bg: Color,
+    #[serde(deserialize_with = "color")]
+    pub infeasible_fg: Color,
+    #[serde(deserialize_with = "color")]
+    pub infeasible_bg: Color,
+    #[serde(deserialize_with = "color")]
+    pub selected_fg: Color,
+    #[serde(deserialize_with = "color")]
+    pub selected_bg: Color,
+    #[serde(deserialize_with = "color")]
+    pub error_fg: Color,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            tick_rate: 250,
+            labels: false,
+            library: None,
+            solver: SolverConfig::default(),
+            clipboard: ClipboardConfig::default(),
+            export: ExportConfig::default(),
+            serve: ServeConfig::default(),
+            keys: Keymap::default(),
+            theme: Theme::default(),
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            cursor_fg: Color::Green,
+            cursor_bg: Color::White,
+            solved_fg: Color::Gray,
+            solved_bg: Color::LightYellow,
+            infeasible_fg: Color::Gray,
+            infeasible_bg: Color::Red,
+            selected_fg: Color::Black,
+            selected_bg: Color::White,
+            error_fg: Color::Red,
+        }
+    }
+}
+
+impl Config {
+    /// Default location of the config file.
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("sudoku").join("config.toml"))
+    }
+
+    /// Loads the config from `path`, or from the default location if none is given.
+    ///
+    /// A missing file at the default location yields the default config.
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => match Self::path() {
+                Some(path) if path.exists() => path,
+                _ => return Ok(Self::default()),
+            },
+        };
+        let content = fs::read_to_string(&path)
+            .wrap_err_with(|| format!("failed to read config file {}", path.display()))?;
+        Self::parse(&content).wrap_err_with(|| format!("invalid config file {}", path.display()))
+    }
+
+    pub fn parse(content: &str) -> Result<Self> {
+        let config: Config = toml::from_str(content)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.tick_rate == 0 {
+            bail!("tick_rate: must be at least 1 millisecond");
+        }
+        let limit = self.serve.time_limit;
+        if limit <= 0.0 || Duration::try_from_secs_f64(limit).is_err() {
+            bail!(
+                "serve.time_limit: must be a positive number of seconds, got {}",
+                limit
+            );
+        }
+        validate_solver(&self.solver)
+    }
+}
+
+fn validate_solver(solver: &SolverConfig) -> Result<()> {
+    if let Some(path) = &solver.path {
+        // a bare name like `scip` is looked up on PATH when it is run
+        if path.components().count() > 1 {
+            if !path.is_file() {
+                bail!("solver.path: {} is not a file", path.display());
+            }
+        } else if !on_path(path) {
+            bail!("solver.path: {} is not found on PATH", path.display());
+        }
+    }
+    if let Some(limit) = solver.time_limit {
+        if limit <= 0.0 || limit.is_nan() {
+            bail!("solver.time_limit: must be positive, got {}", limit);
+        }
+    }
+    for (name, value) in &solver.params {
+        match value {
+            toml::Value::Array(_) | toml::Value::Table(_) | toml::Value::Datetime(_) => {
+                bail!(
+                    "solver.params.{}: expected a number, boolean or string",
+                    name
+                )
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Whether an executable of this name is in one of the directories of PATH.
+fn on_path(name: &Path) -> bool {
+    let mut exe = name.as_os_str().to_owned();
+    exe.push(std::env::consts::EXE_SUFFIX);
+    std::env::var_os("PATH").is_some_and(|paths| {
+        std::env::split_paths(&paths)
+            .any(|dir| dir.join(name).is_file() || dir.join(&exe).is_file())
+    })
+}
+
+fn color<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    s.parse()
+        .map_err(|_| serde::de::Error::custom(format!("unknown color `{}`", s)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let config = Config::parse(
+            r##"
+tick_rate = 100
+
+[solver]
+time_limit = 30
+params = { "limits/gap" = 0.5, "misc/catchctrlc" = false }
+
+[keys]
+preset = "vim"
+quit = ["ctrl-q"]
+
+[theme]
+cursor_bg = "#102030"
+"##,
+        )
+        .unwrap();
+        assert_eq!(config.tick_rate, 100);
+        assert_eq!(config.theme.cursor_bg, Color::Rgb(0x10, 0x20, 0x30));
+        assert_eq!(config.theme.cursor_fg, Color::Green);
+        assert_eq!(
+            config.solver.settings(),
+            "limits/time = 30\nlimits/gap = 0.5\nmisc/catchctrlc = FALSE\n"
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(Config::parse("tick_rate = 0").is_err());
+        assert!(Config::parse("[theme]\ncursor_fg = \"blurple\"").is_err());
+        assert!(Config::parse("[keys]\nquit = [\"hyper-q\"]").is_err());
+        assert!(Config::parse("[solver]\nbackend = \"gurobi\"").is_err());
+        assert!(Config::parse("[solver]\ntime_limit = -1").is_err());
+        assert!(Config::parse("[serve]\ntime_limit = -1").is_err());
+        assert!(Config::parse("[serve]\ntime_limit = 1e30").is_err());
+        assert!(Config::parse("[serve]\ntime_limit = nan").is_err());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_solver_path() {
+        assert!(Config::parse("[solver]\npath = \"sh\"").is_ok());
+        assert!(Config::parse("[solver]\npath = \"/bin/sh\"").is_ok());
+        let err = Config::parse("[solver]\npath = \"no-such-scip\"").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "solver.path: no-such-scip is not found on PATH"
+        );
+        assert!(Config::parse("[solver]\npath = \"./no-such-scip\"").is_err());
+    }
+}
@@ -8,7 +8,7 @@ use color_eyre::Result;
 use crossterm::event::{self, Event as CrosstermEvent, KeyEvent, MouseEvent};
 
 /// Terminal events.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub enum Event {
     /// Terminal tick.
     Tick,
@@ -16,10 +16,12 @@ pub enum Event {
     Key(KeyEvent),
     /// Mouse click/scroll.
     Mouse(MouseEvent),
-    /// Terminal resize.
-    Resize(u16, u16),
+    /// Terminal resize, picked up by the next draw.
+    Resize,
     FocusGained,
     FocusLost,
+    /// Text pasted all at once, with bracketed paste.
+    Paste(String),
 }
 
 /// Terminal event handler.
@@ -59,10 +61,10 @@ impl EventHandler {
                                 }
                             }
                             CrosstermEvent::Mouse(e) => sender.send(Event::Mouse(e)),
-                            CrosstermEvent::Resize(w, h) => sender.send(Event::Resize(w, h)),
+                            CrosstermEvent::Resize(_, _) => sender.send(Event::Resize),
                             CrosstermEvent::FocusGained => sender.send(Event::FocusGained),
                             CrosstermEvent::FocusLost => sender.send(Event::FocusLost),
-                            _ => unimplemented!(),
+                            CrosstermEvent::Paste(text) => sender.send(Event::Paste(text)),
                         }
                         .expect("failed to send terminal event")
                     }
@@ -0,0 +1,491 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::{
+    eyre::{bail, WrapErr},
+    Result,
+};
+use serde::Deserialize;
+
+use sudoku_core::{
+    format::parse_collection,
+    logic::Unit,
+    solver::{self, Backend, SolverConfig},
+    Grid,
+};
+
+use crate::{batch::read_input, cli::ExportArgs};
+
+/// Width and height of an A4 page in points.
+pub const PAGE: (f64, f64) = (595.28, 841.89);
+const CELL: f64 = 50.0;
+/// Line widths, as a fraction of the cell size.
+const THIN: f64 = 0.015;
+const THICK: f64 = 0.05;
+/// Gray level of solution digits, 0 is black.
+const LIGHT: f64 = 0.6;
+
+/// File format of an export.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    Svg,
+    #[default]
+    Pdf,
+}
+
+/// Settings of the `[export]` section, used by the TUI.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExportConfig {
+    pub format: Format,
+    /// Print the solution in a lighter color when there is one.
+    pub solution: bool,
+    /// Directory the files are written to, the current one when unset.
+    pub dir: Option<PathBuf>,
+}
+
+/// A puzzle as it goes on paper.
+#[derive(Debug, Clone)]
+pub struct Sheet {
+    pub title: String,
+    /// Printed under the title, e.g. the source or rating.
+    pub meta: String,
+    pub puzzel: Grid,
+    /// Filled into the empty cells in a lighter color.
+    pub solution: Option<Grid>,
+}
+
+/// Something to draw, in points from the top left of the page.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    Line {
+        from: (f64, f64),
+        to: (f64, f64),
+        width: f64,
+    },
+    /// Text starting at `at`, on its baseline.
+    Text {
+        at: (f64, f64),
+        size: f64,
+        text: String,
+        bold: bool,
+        gray: f64,
+    },
+}
+
+impl Format {
+    /// The format of a file name, by its extension.
+    pub fn of(path: &Path) -> Result<Self> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("svg") => Ok(Format::Svg),
+            Some(ext) if ext.eq_ignore_ascii_case("pdf") => Ok(Format::Pdf),
+            _ => bail!(
+                "can't tell the format of {}, use .svg or .pdf",
+                path.display()
+            ),
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Svg => "svg",
+            Format::Pdf => "pdf",
+        }
+    }
+}
+
+/// The region of every cell, so borders between regions are drawn thick.
+fn regions() -> [[usize; 9]; 9] {
+    let mut regions = [[0; 9]; 9];
+    for (n, unit) in Unit::all()
+        .filter(|unit| matches!(unit, Unit::Box(_)))
+        .enumerate()
+    {
+        for (row, column) in unit.cells() {
+            regions[row][column] = n;
+        }
+    }
+    regions
+}
+
+/// Width of a digit in Helvetica, as a fraction of the font size; every digit
+/// has the same width.
+const DIGIT_WIDTH: f64 = 0.556;
+
+/// Everything on the page, shared by the SVG and PDF writers.
+fn shapes(sheet: &Sheet) -> Vec<Shape> {
+    let size = 9.0 * CELL;
+    let left = (PAGE.0 - size) / 2.0;
+    let mut shapes = vec![Shape::Text {
+        at: (left, 100.0),
+        size: 24.0,
+        text: sheet.title.clone(),
+        bold: true,
+        gray: 0.0,
+    }];
+    if !sheet.meta.is_empty() {
+        shapes.push(Shape::Text {
+            at: (left, 125.0),
+            size: 12.0,
+            text: sheet.meta.clone(),
+            bold: false,
+            gray: 0.0,
+        });
+    }
+    draw_grid(
+        &mut shapes,
+        (left, 150.0),
+        CELL,
+        &sheet.puzzel,
+        sheet.solution.as_ref(),
+    );
+    shapes
+}
+
+/// Draws a grid with its top left corner at `origin`: the givens in bold and
+/// the other digits of the solution, if there is one, in gray.
+pub fn draw_grid(
+    shapes: &mut Vec<Shape>,
+    origin: (f64, f64),
+    cell: f64,
+    puzzel: &Grid,
+    solution: Option<&Grid>,
+) {
+    let corner = |row: usize, column: usize| {
+        (
+            origin.0 + column as f64 * cell,
+            origin.1 + row as f64 * cell,
+        )
+    };
+    let regions = regions();
+    // thin lines first, so thick ones are drawn over them
+    let mut thick = Vec::new();
+    for i in 0..9 {
+        for j in 0..9 {
+            let mut edges = Vec::new();
+            if j < 8 {
+                edges.push((
+                    (corner(i, j + 1), corner(i + 1, j + 1)),
+                    regions[i][j] != regions[i][j + 1],
+                ));
+            }
+            if i < 8 {
+                edges.push((
+                    (corner(i + 1, j), corner(i + 1, j + 1)),
+                    regions[i][j] != regions[i + 1][j],
+                ));
+            }
+            for ((from, to), border) in edges {
+                if border {
+                    thick.push((from, to));
+                } else {
+                    shapes.push(Shape::Line {
+                        from,
+                        to,
+                        width: THIN * cell,
+                    });
+                }
+            }
+        }
+    }
+    thick.extend([
+        (corner(0, 0), corner(0, 9)),
+        (corner(9, 0), corner(9, 9)),
+        (corner(0, 0), corner(9, 0)),
+        (corner(0, 9), corner(9, 9)),
+    ]);
+    shapes.extend(thick.into_iter().map(|(from, to)| Shape::Line {
+        from,
+        to,
+        width: THICK * cell,
+    }));
+
+    let digit_size = 0.6 * cell;
+    for i in 0..9 {
+        for j in 0..9 {
+            let (digit, gray) = match (puzzel[i][j], solution) {
+                (Some(k), _) => (k, 0.0),
+                (None, Some(solution)) => match solution[i][j] {
+                    Some(k) => (k, LIGHT),
+                    None => continue,
+                },
+                (None, None) => continue,
+            };
+            let (x, y) = corner(i, j);
+            shapes.push(Shape::Text {
+                at: (
+                    x + (cell - DIGIT_WIDTH * digit_size) / 2.0,
+                    y + (cell + 0.7 * digit_size) / 2.0,
+                ),
+                size: digit_size,
+                text: digit.to_string(),
+                bold: gray == 0.0,
+                gray,
+            });
+        }
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Draws the sheet as an A4 SVG image.
+pub fn svg(sheet: &Sheet) -> String {
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"210mm\" height=\"297mm\" viewBox=\"0 0 {} {}\">\n\
+         <rect width=\"100%\" height=\"100%\" fill=\"white\"/>\n",
+        PAGE.0, PAGE.1
+    );
+    for shape in shapes(sheet) {
+        match shape {
+            Shape::Line { from, to, width } => svg += &format!(
+                "<line x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\" stroke=\"black\" stroke-width=\"{}\" stroke-linecap=\"square\"/>\n",
+                from.0, from.1, to.0, to.1, width
+            ),
+            Shape::Text {
+                at,
+                size,
+                text,
+                bold,
+                gray,
+            } => {
+                let weight = if bold { " font-weight=\"bold\"" } else { "" };
+                let gray = (gray * 255.0).round();
+                svg += &format!(
+                    "<text x=\"{:.2}\" y=\"{:.2}\" font-family=\"Helvetica, Arial, sans-serif\" font-size=\"{}\"{} fill=\"rgb({},{},{})\">{}</text>\n",
+                    at.0, at.1, size, weight, gray, gray, gray, escape_xml(&text)
+                )
+            }
+        }
+    }
+    svg += "</svg>\n";
+    svg
+}
+
+/// Text as a PDF string literal; the standard fonts only cover Latin-1, so
+/// anything else becomes `?`.
+fn pdf_string(text: &str) -> String {
+    let mut escaped = String::from("(");
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            ' '..='~' => escaped.push(c),
+            c if (c as u32) < 256 => escaped += &format!("\\{:03o}", c as u32),
+            _ => escaped.push('?'),
+        }
+    }
+    escaped.push(')');
+    escaped
+}
+
+/// Draws the sheet as a one page A4 PDF, with the built-in Helvetica fonts.
+pub fn pdf(sheet: &Sheet) -> Vec<u8> {
+    pdf_document(&[shapes(sheet)])
+}
+
+/// The PDF drawing operators of a page.
+fn pdf_content(shapes: &[Shape]) -> String {
+    let mut content = String::from("2 J\n");
+    for shape in shapes {
+        match shape {
+            Shape::Line { from, to, width } => {
+                content += &format!(
+                    "{} w {:.2} {:.2} m {:.2} {:.2} l S\n",
+                    width,
+                    from.0,
+                    PAGE.1 - from.1,
+                    to.0,
+                    PAGE.1 - to.1
+                )
+            }
+            Shape::Text {
+                at,
+                size,
+                text,
+                bold,
+                gray,
+            } => {
+                content += &format!(
+                    "BT /{} {} Tf {} g {:.2} {:.2} Td {} Tj ET\n",
+                    if *bold { "F2" } else { "F1" },
+                    size,
+                    gray,
+                    at.0,
+                    PAGE.1 - at.1,
+                    pdf_string(text)
+                )
+            }
+        }
+    }
+    content
+}
+
+/// An A4 PDF with one page per list of shapes.
+pub fn pdf_document(pages: &[Vec<Shape>]) -> Vec<u8> {
+    // the catalog, the page tree and two fonts, then a page and its
+    // content for every page
+    let kids: Vec<String> = (0..pages.len())
+        .map(|n| format!("{} 0 R", 5 + 2 * n))
+        .collect();
+    let mut objects = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            kids.join(" "),
+            pages.len()
+        ),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+            .to_string(),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>"
+            .to_string(),
+    ];
+    for (n, shapes) in pages.iter().enumerate() {
+        let content = pdf_content(shapes);
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+             /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+            PAGE.0,
+            PAGE.1,
+            6 + 2 * n
+        ));
+        objects.push(format!(
+            "<< /Length {} >>\nstream\n{}endstream",
+            content.len(),
+            content
+        ));
+    }
+    write_pdf(&objects)
+}
+
+/// Numbers the objects from 1 and adds the cross-reference table.
+fn write_pdf(objects: &[String]) -> Vec<u8> {
+    let mut pdf = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::new();
+    for (n, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend(format!("{} 0 obj\n{}\nendobj\n", n + 1, object).bytes());
+    }
+    let xref = pdf.len();
+    pdf.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).bytes());
+    for offset in offsets {
+        pdf.extend(format!("{:010} 00000 n \n", offset).bytes());
+    }
+    pdf.extend(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref
+        )
+        .bytes(),
+    );
+    pdf
+}
+
+/// Writes the sheet in the format given by the file extension.
+pub fn write(path: &Path, sheet: &Sheet) -> Result<()> {
+    let bytes = match Format::of(path)? {
+        Format::Svg => svg(sheet).into_bytes(),
+        Format::Pdf => pdf(sheet),
+    };
+    fs::write(path, bytes).wrap_err_with(|| format!("failed to write {}", path.display()))
+}
+
+/// Draws one puzzle of the input, solved by the native backend if the
+/// solution is wanted.
+pub fn run(args: &ExportArgs) -> Result<()> {
+    let input = read_input(args.input.as_deref())?;
+    let entries = parse_collection(&input)?;
+    let index = args.puzzle.get() - 1;
+    let Some(entry) = entries.get(index) else {
+        bail!("there are only {} puzzles", entries.len());
+    };
+    let solution = if args.solution {
+        let config = SolverConfig {
+            backend: Backend::Native,
+            ..SolverConfig::default()
+        };
+        Some(solver::solve_with(&entry.grid, &config, &mut |_| {})?)
+    } else {
+        None
+    };
+    let sheet = Sheet {
+        title: args
+            .title
+            .clone()
+            .unwrap_or_else(|| format!("Puzzle {}", index + 1)),
+        meta: entry.meta.clone(),
+        puzzel: entry.grid,
+        solution,
+    };
+    write(&args.output, &sheet)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use sudoku_core::format::parse_grid;
+
+    #[test]
+    fn test_export() {
+        let puzzel = parse_grid(
+            "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79",
+        )
+        .unwrap();
+        let solution = parse_grid(
+            "534678912672195348198342567859761423426853791713924856961537284287419635345286179",
+        )
+        .unwrap();
+        let sheet = Sheet {
+            title: "Puzzle 1".to_string(),
+            meta: "easy <1.2>".to_string(),
+            puzzel,
+            solution: Some(solution),
+        };
+
+        let shapes = shapes(&sheet);
+        let width = |w: f64| {
+            shapes
+                .iter()
+                .filter(|shape| matches!(shape, Shape::Line { width, .. } if *width == w * CELL))
+                .count()
+        };
+        // 2 * 9 * 6 cell edges inside the boxes, 2 * 9 * 2 between boxes and the border
+        assert_eq!(width(THIN), 108);
+        assert_eq!(width(THICK), 36 + 4);
+        let digits = |g: f64| {
+            shapes
+                .iter()
+                .filter(|shape| matches!(shape, Shape::Text { size, gray, .. } if *size == 30.0 && *gray == g))
+                .count()
+        };
+        assert_eq!((digits(0.0), digits(LIGHT)), (30, 51));
+
+        let svg = svg(&sheet);
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains(">easy &lt;1.2&gt;</text>"));
+
+        let pdf = pdf(&sheet);
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.starts_with("%PDF-1.4"));
+        assert!(text.ends_with("%%EOF\n"));
+        let xref = text.find("\nxref\n").unwrap() + 1;
+        assert!(text.contains(&format!("startxref\n{}\n", xref)));
+        let second = text.find("2 0 obj").unwrap();
+        assert!(text.contains(&format!("{:010} 00000 n", second)));
+
+        assert_eq!(pdf_string("a(b) é ✓"), "(a\\(b\\) \\351 ?)");
+        assert_eq!(Format::of(Path::new("out.SVG")).unwrap(), Format::Svg);
+        assert!(Format::of(Path::new("out.png")).is_err());
+    }
+}
@@ -0,0 +1,556 @@
+use std::collections::{HashMap, HashSet};
+
+use clap::ValueEnum;
+use color_eyre::{
+    eyre::{bail, eyre, WrapErr},
+    Result,
+};
+use serde_json::{json, Map, Value};
+
+use sudoku_core::{
+    format::{self, format_grid},
+    Grid,
+};
+
+use crate::{
+    batch::read_input,
+    cli::{ImportArgs, LinkArgs},
+};
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Keys of an f-puzzles file that hold no constraint.
+const METADATA: [&str; 8] = [
+    "size",
+    "title",
+    "author",
+    "ruleset",
+    "grid",
+    "solution",
+    "highlightConflicts",
+    "disabledlogic",
+];
+
+/// The web tool a link opens in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Site {
+    #[default]
+    Fpuzzles,
+    Sudokupad,
+}
+
+/// A puzzle read from f-puzzles JSON or a link.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Import {
+    pub grid: Grid,
+    /// The title and author, if any.
+    pub meta: String,
+    /// Constraints a classic sudoku can't hold, with how many there were.
+    pub dropped: Vec<(String, usize)>,
+}
+
+#[derive(Default)]
+struct BitWriter {
+    encoded: String,
+    value: usize,
+    position: usize,
+}
+
+impl BitWriter {
+    /// Writes the lowest `bits` bits of `value`, lowest first.
+    fn write(&mut self, mut value: usize, bits: u32) {
+        for _ in 0..bits {
+            self.value = self.value << 1 | value & 1;
+            value >>= 1;
+            if self.position == 5 {
+                self.encoded.push(BASE64[self.value] as char);
+                self.position = 0;
+                self.value = 0;
+            } else {
+                self.position += 1;
+            }
+        }
+    }
+
+    fn finish(mut self) -> String {
+        loop {
+            self.value <<= 1;
+            if self.position == 5 {
+                self.encoded.push(BASE64[self.value] as char);
+                break;
+            }
+            self.position += 1;
+        }
+        while !self.encoded.len().is_multiple_of(4) {
+            self.encoded.push('=');
+        }
+        self.encoded
+    }
+}
+
+struct BitReader {
+    values: Vec<usize>,
+    value: usize,
+    position: usize,
+    index: usize,
+}
+
+impl BitReader {
+    fn read(&mut self, bits: u32) -> usize {
+        let mut read = 0;
+        for i in 0..bits {
+            let bit = self.value & self.position != 0;
+            self.position >>= 1;
+            if self.position == 0 {
+                self.position = 32;
+                self.value = self.values.get(self.index).copied().unwrap_or(0);
+                self.index += 1;
+            }
+            read |= (bit as usize) << i;
+        }
+        read
+    }
+}
+
+/// The state of lz-string's compressor between phrases.
+#[derive(Default)]
+struct Compressor {
+    writer: BitWriter,
+    dictionary: HashMap<Vec<u16>, usize>,
+    /// Single characters that are in the dictionary but not yet written out.
+    pending: HashSet<Vec<u16>>,
+    enlarge_in: usize,
+    bits: u32,
+}
+
+impl Compressor {
+    /// Widens the codes once the dictionary outgrows them.
+    fn count_code(&mut self) {
+        self.enlarge_in -= 1;
+        if self.enlarge_in == 0 {
+            self.enlarge_in = 1 << self.bits;
+            self.bits += 1;
+        }
+    }
+
+    fn emit(&mut self, w: &[u16]) {
+        if self.pending.remove(w) {
+            if w[0] < 256 {
+                self.writer.write(0, self.bits);
+                self.writer.write(w[0] as usize, 8);
+            } else {
+                self.writer.write(1, self.bits);
+                self.writer.write(w[0] as usize, 16);
+            }
+            self.count_code();
+        } else {
+            self.writer.write(self.dictionary[w], self.bits);
+        }
+        self.count_code();
+    }
+}
+
+/// lz-string's `compressToBase64`, which both tools use for their links.
+pub fn compress(text: &str) -> String {
+    let mut compressor = Compressor {
+        enlarge_in: 2,
+        bits: 2,
+        ..Compressor::default()
+    };
+    let mut w = Vec::new();
+    for c in text.encode_utf16() {
+        if !compressor.dictionary.contains_key(&[c][..]) {
+            let code = compressor.dictionary.len() + 3;
+            compressor.dictionary.insert(vec![c], code);
+            compressor.pending.insert(vec![c]);
+        }
+        let mut wc = w.clone();
+        wc.push(c);
+        if compressor.dictionary.contains_key(&wc) {
+            w = wc;
+        } else {
+            compressor.emit(&w);
+            let code = compressor.dictionary.len() + 3;
+            compressor.dictionary.insert(wc, code);
+            w = vec![c];
+        }
+    }
+    if !w.is_empty() {
+        compressor.emit(&w);
+    }
+    compressor.writer.write(2, compressor.bits);
+    compressor.writer.finish()
+}
+
+/// lz-string's `decompressFromBase64`, `None` if the text is not its output.
+pub fn decompress(text: &str) -> Option<String> {
+    let values = text
+        .bytes()
+        .map(|b| match b {
+            b'=' => Some(0),
+            b => BASE64.iter().position(|&c| c == b),
+        })
+        .collect::<Option<Vec<_>>>()?;
+    let length = values.len();
+    let mut reader = BitReader {
+        value: *values.first()?,
+        values,
+        position: 32,
+        index: 1,
+    };
+    // codes 0 to 2 are taken by the markers
+    let mut dictionary: Vec<Vec<u16>> = vec![Vec::new(); 3];
+    let (mut enlarge_in, mut bits) = (4, 3);
+    let c = match reader.read(2) {
+        0 => reader.read(8),
+        1 => reader.read(16),
+        _ => return Some(String::new()),
+    };
+    let mut w = vec![c as u16];
+    dictionary.push(w.clone());
+    let mut decompressed = w.clone();
+    loop {
+        if reader.index > length {
+            return None;
+        }
+        let mut code = reader.read(bits);
+        match code {
+            0 | 1 => {
+                let c = reader.read(if code == 0 { 8 } else { 16 });
+                dictionary.push(vec![c as u16]);
+                code = dictionary.len() - 1;
+                enlarge_in -= 1;
+            }
+            2 => return String::from_utf16(&decompressed).ok(),
+            _ => {}
+        }
+        if enlarge_in == 0 {
+            enlarge_in = 1 << bits;
+            bits += 1;
+        }
+        let entry = match dictionary.get(code) {
+            Some(entry) => entry.clone(),
+            None if code == dictionary.len() => [&w[..], &w[..1]].concat(),
+            None => return None,
+        };
+        decompressed.extend(&entry);
+        dictionary.push([&w[..], &entry[..1]].concat());
+        enlarge_in -= 1;
+        w = entry;
+        if enlarge_in == 0 {
+            enlarge_in = 1 << bits;
+            bits += 1;
+        }
+    }
+}
+
+fn percent_decode(s: &str) -> String {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut rest = s.as_bytes();
+    while let Some((&b, tail)) = rest.split_first() {
+        let hex = tail
+            .get(..2)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (b, hex) {
+            (b'%', Some(decoded)) => {
+                bytes.push(decoded);
+                rest = &tail[2..];
+            }
+            _ => {
+                bytes.push(b);
+                rest = tail;
+            }
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+fn percent_encode(s: &str) -> String {
+    s.replace('+', "%2B")
+        .replace('/', "%2F")
+        .replace('=', "%3D")
+}
+
+/// The compressed puzzle of a link: the `load` or `puzzle` parameter, or
+/// the path, as in `https://sudokupad.app/fpuzzles...`.
+fn payload(link: &str) -> &str {
+    let query = link.split_once('?').map(|(_, query)| query);
+    let parameter = query.and_then(|query| {
+        query
+            .split(['&', '#'])
+            .find_map(|pair| match pair.split_once('=') {
+                Some(("load" | "puzzle", value)) => Some(value),
+                _ => None,
+            })
+    });
+    match parameter {
+        Some(value) => value,
+        // the data may hold `/`, so it is the whole path
+        None => match link.split_once("://") {
+            Some((_, rest)) => {
+                let rest = rest.split(['?', '#']).next().unwrap_or(rest);
+                rest.split_once('/').map_or("", |(_, path)| path)
+            }
+            None => link,
+        },
+    }
+}
+
+/// Reads f-puzzles JSON, an f-puzzles link, a SudokuPad link made from
+/// f-puzzles data, or the compressed data on its own.
+pub fn import(text: &str) -> Result<Import> {
+    let text = text.trim();
+    if text.starts_with('{') {
+        return from_json(text);
+    }
+    // a `+` of the data turns into a space when a link is unescaped
+    let data = percent_decode(payload(text)).replace(' ', "+");
+    let data = data.strip_prefix("fpuzzles").unwrap_or(&data);
+    if data.starts_with("scl") || data.starts_with("ctc") {
+        bail!("SudokuPad's own format is not supported, share the puzzle as f-puzzles");
+    }
+    let json = decompress(data).ok_or_else(|| {
+        eyre!("not an f-puzzles or SudokuPad link; short links need the SudokuPad server")
+    })?;
+    from_json(&json)
+}
+
+/// Reads a puzzle as pasted or posted: a grid in any layout
+/// [`format::parse_puzzle`] takes, f-puzzles JSON or a link. When it is
+/// none of them, the error is the one about the grid.
+pub fn read_puzzle(text: &str) -> Result<Import> {
+    format::parse_puzzle(text)
+        .map(|grid| Import {
+            grid,
+            meta: String::new(),
+            dropped: Vec::new(),
+        })
+        .or_else(|err| import(text).map_err(|_| err.into()))
+}
+
+/// Takes the givens of an f-puzzles file, and lists every constraint that
+/// had to be left out.
+pub fn from_json(json: &str) -> Result<Import> {
+    let puzzle: Map<String, Value> =
+        serde_json::from_str(json).wrap_err("not an f-puzzles puzzle")?;
+    match puzzle.get("size").and_then(Value::as_u64) {
+        Some(9) => {}
+        Some(size) => bail!("only 9x9 puzzles are supported, this one is {0}x{0}", size),
+        None => bail!("not an f-puzzles puzzle, it has no size"),
+    }
+    let rows = puzzle
+        .get("grid")
+        .and_then(Value::as_array)
+        .filter(|rows| rows.len() == 9)
+        .ok_or_else(|| eyre!("the grid is not 9 rows"))?;
+    let mut grid = [[None; 9]; 9];
+    let mut regions = [[None; 9]; 9];
+    for (i, row) in rows.iter().enumerate() {
+        let cells = row
+            .as_array()
+            .filter(|cells| cells.len() == 9)
+            .ok_or_else(|| eyre!("row {} is not 9 cells", i + 1))?;
+        for (j, cell) in cells.iter().enumerate() {
+            let value = cell.get("value").and_then(Value::as_u64);
+            let given = cell.get("given").and_then(Value::as_bool) == Some(true);
+            match value {
+                Some(k @ 1..=9) if given => grid[i][j] = Some(k as u8),
+                Some(k) if given => bail!("R{}C{} is given as {}", i + 1, j + 1, k),
+                _ => {}
+            }
+            regions[i][j] = match cell.get("region") {
+                Some(region) => region.as_u64(),
+                None => Some((3 * (i / 3) + j / 3) as u64),
+            };
+        }
+    }
+
+    let mut dropped = Vec::new();
+    // the regions may be numbered in any order, but must be the boxes
+    let mut numbers = HashSet::new();
+    let boxes = (0..9).all(|b| {
+        let cells: HashSet<Option<u64>> = (0..9)
+            .map(|n| regions[3 * (b / 3) + n / 3][3 * (b % 3) + n % 3])
+            .collect();
+        // a cell with a null region is in none
+        cells.len() == 1 && cells.into_iter().all(|n| n.is_some() && numbers.insert(n))
+    });
+    if !boxes {
+        dropped.push(("irregular regions".to_string(), 1));
+    }
+    for (key, value) in &puzzle {
+        if METADATA.contains(&key.as_str()) {
+            continue;
+        }
+        let count = match value {
+            Value::Array(constraints) => constraints.len(),
+            Value::Bool(on) => *on as usize,
+            Value::Null => 0,
+            _ => 1,
+        };
+        if count > 0 {
+            dropped.push((key.clone(), count));
+        }
+    }
+
+    let field = |key: &str| puzzle.get(key).and_then(Value::as_str).unwrap_or("").trim();
+    let meta = match (field("title"), field("author")) {
+        (title, "") => title.to_string(),
+        ("", author) => format!("by {}", author),
+        (title, author) => format!("{} by {}", title, author),
+    };
+    Ok(Import {
+        grid,
+        meta,
+        dropped,
+    })
+}
+
+/// The grid as an f-puzzles file.
+pub fn to_json(grid: &Grid, title: &str) -> String {
+    let rows: Vec<Value> = grid
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|cell| match cell {
+                    Some(k) => json!({ "value": k, "given": true }),
+                    None => json!({}),
+                })
+                .collect()
+        })
+        .collect();
+    let mut puzzle = json!({ "size": 9, "grid": rows });
+    if !title.is_empty() {
+        puzzle["title"] = json!(title);
+    }
+    puzzle.to_string()
+}
+
+/// A link that opens the grid in f-puzzles or SudokuPad.
+pub fn link(grid: &Grid, title: &str, site: Site) -> String {
+    let data = percent_encode(&compress(&to_json(grid, title)));
+    match site {
+        Site::Fpuzzles => format!("https://www.f-puzzles.com/?load={}", data),
+        Site::Sudokupad => format!("https://sudokupad.app/?puzzle=fpuzzles{}", data),
+    }
+}
+
+pub fn describe(dropped: &[(String, usize)]) -> String {
+    dropped
+        .iter()
+        .map(|(constraint, count)| format!("{} ({})", constraint, count))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Prints the puzzles of f-puzzles JSON or links as a collection, and on
+/// stderr the constraints that were dropped.
+pub fn run_import(args: &ImportArgs) -> Result<()> {
+    let input = read_input(args.input.as_deref())?;
+    // JSON may span lines, links are one to a line
+    let sources: Vec<(usize, &str)> = if input.trim_start().starts_with('{') {
+        vec![(1, input.as_str())]
+    } else {
+        input
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty() && !line.trim().starts_with('#'))
+            .map(|(n, line)| (n + 1, line))
+            .collect()
+    };
+    for (n, source) in sources {
+        let import = import(source).wrap_err_with(|| format!("line {}", n))?;
+        if !import.dropped.is_empty() {
+            eprintln!("line {}: dropped {}", n, describe(&import.dropped));
+        }
+        if import.meta.is_empty() {
+            println!("{}", format_grid(&import.grid));
+        } else {
+            println!("{} {}", format_grid(&import.grid), import.meta);
+        }
+    }
+    Ok(())
+}
+
+/// Prints a link for every puzzle of a collection.
+pub fn run_link(args: &LinkArgs) -> Result<()> {
+    let entries = format::parse_collection(&read_input(args.input.as_deref())?)?;
+    for entry in entries {
+        println!("{}", link(&entry.grid, &entry.meta, args.site));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use sudoku_core::format::parse_grid;
+
+    #[test]
+    fn test_fpuzzles() {
+        for text in [
+            "",
+            "a",
+            "ababababab",
+            "{\"size\":9,\"grid\":[[{}]]}",
+            "été ∑ 𝄞",
+        ] {
+            assert_eq!(decompress(&compress(text)).as_deref(), Some(text));
+        }
+        // the start every f-puzzles link shares
+        assert_eq!(
+            compress("{\"size\":9,\"grid\":[[{"),
+            "N4IgzglgXgpiBcBOANCA5gJwgEwQbT2CA==="
+        );
+        assert_eq!(decompress("not base64!"), None);
+
+        let grid = parse_grid(
+            "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79",
+        )
+        .unwrap();
+        for site in [Site::Fpuzzles, Site::Sudokupad] {
+            let imported = import(&link(&grid, "Classic", site)).unwrap();
+            assert_eq!(imported.grid, grid);
+            assert_eq!(imported.meta, "Classic");
+            assert!(imported.dropped.is_empty());
+        }
+        // unescaped links, and the data on its own
+        let data = compress(&to_json(&grid, ""));
+        assert_eq!(import(&data).unwrap().grid, grid);
+        let unescaped = format!("https://sudokupad.app/fpuzzles{}", data.replace('+', " "));
+        assert_eq!(import(&unescaped).unwrap().grid, grid);
+
+        let mut puzzle: Value = serde_json::from_str(&to_json(&grid, "")).unwrap();
+        puzzle["author"] = json!("Someone");
+        puzzle["killercage"] = json!([{ "cells": ["R1C1", "R1C2"], "value": "8" }]);
+        puzzle["diagonal+"] = json!(true);
+        puzzle["antiknight"] = json!(false);
+        puzzle["grid"][0][3]["region"] = json!(0);
+        let imported = from_json(&puzzle.to_string()).unwrap();
+        assert_eq!(imported.grid, grid);
+        assert_eq!(imported.meta, "by Someone");
+        assert_eq!(
+            describe(&imported.dropped),
+            "irregular regions (1), diagonal+ (1), killercage (1)"
+        );
+
+        // boxes numbered in another order are still boxes
+        for (i, row) in puzzle["grid"]
+            .as_array_mut()
+            .unwrap()
+            .iter_mut()
+            .enumerate()
+        {
+            for (j, cell) in row.as_array_mut().unwrap().iter_mut().enumerate() {
+                cell["region"] = json!(8 - (3 * (i / 3) + j / 3));
+            }
+        }
+        assert_eq!(from_json(&puzzle.to_string()).unwrap().dropped.len(), 2);
+
+        puzzle["size"] = json!(6);
+        assert!(from_json(&puzzle.to_string()).is_err());
+        assert!(import("https://sudokupad.app/sclABC").is_err());
+    }
+}
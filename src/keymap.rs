@@ -0,0 +1,508 @@
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Deserializer};
+
+/// A key combination such as `q`, `enter` or `ctrl-c`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Key {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+/// Something the user can do with a key press.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Action {
+    Quit,
+    Solve,
+    Up,
+    Down,
+    Left,
+    Right,
+    /// Writes a digit into the selected cell.
+    Set(u8),
+    Clear,
+    Undo,
+    Redo,
+    /// Turns the grid a quarter clockwise.
+    Rotate,
+    /// Mirrors the grid left to right.
+    Mirror,
+    Transpose,
+    /// Swaps the selected row with the next one of its band.
+    SwapRow,
+    SwapBand,
+    SwapStack,
+    /// Relabels the digits at random.
+    Relabel,
+    /// Copies the puzzle to the clipboard.
+    CopyPuzzle,
+    CopySolution,
+    CopyBoth,
+    /// Writes the board to a printable SVG or PDF file.
+    Export,
+    /// Starts or stops playing the puzzle on the board.
+    Play,
+    /// Turns flagging of wrong entries on or off.
+    Check,
+    /// Reveals a cell while playing.
+    Hint,
+    /// Shows or hides the list of the open puzzle collection.
+    Collection,
+    /// Opens the next puzzle of the collection.
+    Next,
+    Previous,
+    /// Shows or hides the solver log.
+    Log,
+    LogUp,
+    LogDown,
+}
+
+/// Built-in sets of bindings that a config can start from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Preset {
+    /// Arrow keys and the number row.
+    #[default]
+    Default,
+    /// `hjkl` movement on top of the default bindings.
+    Vim,
+    /// `wasd` movement with digits on the `uio`/`jkl`/`m,.` block, laid out like a numpad.
+    Wasd,
+}
+
+/// Maps keys to actions.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "RawKeymap")]
+pub struct Keymap {
+    bindings: BTreeMap<Action, Vec<Key>>,
+}
+
+/// The `[keys]` section as written in the config file.
+#[derive(Debug, Deserialize)]
+struct RawKeymap {
+    #[serde(default)]
+    preset: Preset,
+    #[serde(flatten)]
+    bindings: BTreeMap<String, Vec<Key>>,
+}
+
+impl Key {
+    pub const fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Self { code, modifiers }
+    }
+
+    pub fn matches(&self, event: &KeyEvent) -> bool {
+        match (self.code, event.code) {
+            // the shift state is already part of the character
+            (KeyCode::Char(a), KeyCode::Char(b)) => {
+                let modifiers = event.modifiers - KeyModifiers::SHIFT;
+                if modifiers.contains(KeyModifiers::CONTROL) {
+                    a.eq_ignore_ascii_case(&b) && self.modifiers == modifiers
+                } else {
+                    a == b && self.modifiers == modifiers
+                }
+            }
+            (a, b) => a == b && self.modifiers == event.modifiers,
+        }
+    }
+
+    /// The key as [`Key::matches`] sees it, so that keys matching the same
+    /// presses are equal: with ctrl, the case of a character doesn't count.
+    fn normalized(&self) -> Key {
+        match self.code {
+            KeyCode::Char(c) if self.modifiers.contains(KeyModifiers::CONTROL) => {
+                Key::new(KeyCode::Char(c.to_ascii_lowercase()), self.modifiers)
+            }
+            _ => *self,
+        }
+    }
+}
+
+impl Action {
+    fn all() -> impl Iterator<Item = Action> {
+        [
+            Action::Quit,
+            Action::Solve,
+            Action::Up,
+            Action::Down,
+            Action::Left,
+            Action::Right,
+            Action::Clear,
+            Action::Undo,
+            Action::Redo,
+            Action::Rotate,
+            Action::Mirror,
+            Action::Transpose,
+            Action::SwapRow,
+            Action::SwapBand,
+            Action::SwapStack,
+            Action::Relabel,
+            Action::CopyPuzzle,
+            Action::CopySolution,
+            Action::CopyBoth,
+            Action::Export,
+            Action::Play,
+            Action::Check,
+            Action::Hint,
+            Action::Collection,
+            Action::Next,
+            Action::Previous,
+            Action::Log,
+            Action::LogUp,
+            Action::LogDown,
+        ]
+        .into_iter()
+        .chain((1..=9).map(Action::Set))
+    }
+}
+
+impl Keymap {
+    /// The bindings of a preset.
+    pub fn preset(preset: Preset) -> Self {
+        let keys =
+            |keys: &[&str]| -> Vec<Key> { keys.iter().map(|k| k.parse().unwrap()).collect() };
+        let mut bindings = BTreeMap::from([
+            (Action::Quit, keys(&["q", "esc", "ctrl-c"])),
+            (Action::Solve, keys(&["enter"])),
+            (Action::Up, keys(&["up"])),
+            (Action::Down, keys(&["down"])),
+            (Action::Left, keys(&["left"])),
+            (Action::Right, keys(&["right"])),
+            (Action::Clear, keys(&["backspace", "delete"])),
+            (Action::Undo, keys(&["u"])),
+            (Action::Redo, keys(&["ctrl-r"])),
+            (Action::Rotate, keys(&["r"])),
+            (Action::Mirror, keys(&["M"])),
+            (Action::Transpose, keys(&["t"])),
+            (Action::SwapRow, keys(&["J"])),
+            (Action::SwapBand, keys(&["B"])),
+            (Action::SwapStack, keys(&["S"])),
+            (Action::Relabel, keys(&["D"])),
+            (Action::CopyPuzzle, keys(&["Y"])),
+            (Action::CopySolution, keys(&["ctrl-y"])),
+            (Action::CopyBoth, keys(&["alt-y"])),
+            (Action::Export, keys(&["E"])),
+            (Action::Play, keys(&["p"])),
+            (Action::Check, keys(&["c"])),
+            (Action::Hint, keys(&["?"])),
+            (Action::Collection, keys(&["tab"])),
+            (Action::Next, keys(&["n"])),
+            (Action::Previous, keys(&["N"])),
+            (Action::Log, keys(&["L"])),
+            (Action::LogUp, keys(&["pageup"])),
+            (Action::LogDown, keys(&["pagedown"])),
+        ]);
+        for digit in 1..=9 {
+            let key = Key::new(KeyCode::Char((b'0' + digit) as char), KeyModifiers::NONE);
+            bindings.insert(Action::Set(digit), vec![key]);
+        }
+
+        let mut add = |action, extra: &[&str]| {
+            bindings.get_mut(&action).unwrap().extend(keys(extra));
+        };
+        match preset {
+            Preset::Default => {}
+            Preset::Vim => {
+                add(Action::Up, &["k"]);
+                add(Action::Down, &["j"]);
+                add(Action::Left, &["h"]);
+                add(Action::Right, &["l"]);
+                add(Action::Clear, &["x"]);
+            }
+            Preset::Wasd => {
+                add(Action::Up, &["w"]);
+                add(Action::Down, &["s"]);
+                add(Action::Left, &["a"]);
+                add(Action::Right, &["d"]);
+                add(Action::Clear, &["x"]);
+                for (digit, key) in ["m", ",", ".", "j", "k", "l", "u", "i", "o"]
+                    .iter()
+                    .enumerate()
+                {
+                    add(Action::Set(digit as u8 + 1), &[key]);
+                }
+                // `u` enters a digit here, and `q` is too close to `w`
+                bindings.insert(Action::Undo, keys(&["z"]));
+                bindings.insert(Action::Redo, keys(&["y"]));
+                bindings.insert(Action::Quit, keys(&["esc", "ctrl-c"]));
+            }
+        }
+        Self { bindings }
+    }
+
+    /// The action bound to a key press, if any.
+    pub fn action(&self, event: &KeyEvent) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(_, keys)| keys.iter().any(|key| key.matches(event)))
+            .map(|(action, _)| *action)
+    }
+
+    /// The first key bound to an action, for display.
+    pub fn key(&self, action: Action) -> Option<&Key> {
+        self.bindings.get(&action).and_then(|keys| keys.first())
+    }
+
+    /// Fails if a key is bound to more than one action.
+    fn check_conflicts(&self) -> Result<(), KeymapError> {
+        let mut seen: Vec<(Key, Action)> = Vec::new();
+        for (action, keys) in &self.bindings {
+            for key in keys {
+                if let Some((_, other)) = seen.iter().find(|(k, _)| *k == key.normalized()) {
+                    return Err(KeymapError::Conflict(*key, *other, *action));
+                }
+                seen.push((key.normalized(), *action));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::preset(Preset::Default)
+    }
+}
+
+impl TryFrom<RawKeymap> for Keymap {
+    type Error = KeymapError;
+
+    fn try_from(raw: RawKeymap) -> Result<Self, Self::Error> {
+        let mut keymap = Keymap::preset(raw.preset);
+        for (name, keys) in raw.bindings {
+            let action = name.parse()?;
+            keymap.bindings.insert(action, keys);
+        }
+        keymap.check_conflicts()?;
+        Ok(keymap)
+    }
+}
+
+#[derive(Debug)]
+pub enum KeymapError {
+    UnknownKey(String),
+    /// `shift-` on a character, which never matches.
+    Shifted(String),
+    UnknownAction(String),
+    Conflict(Key, Action, Action),
+}
+
+impl fmt::Display for KeymapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeymapError::UnknownKey(key) => write!(f, "unknown key `{}`", key),
+            KeymapError::Shifted(key) => write!(
+                f,
+                "key `{}` never fires, write the shifted character instead, e.g. `M` for `shift-m`",
+                key
+            ),
+            KeymapError::UnknownAction(action) => write!(f, "unknown action `{}`", action),
+            KeymapError::Conflict(key, a, b) => {
+                write!(f, "key `{}` is bound to both `{}` and `{}`", key, a, b)
+            }
+        }
+    }
+}
+
+impl std::error::Error for KeymapError {}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::Quit => write!(f, "quit"),
+            Action::Solve => write!(f, "solve"),
+            Action::Up => write!(f, "up"),
+            Action::Down => write!(f, "down"),
+            Action::Left => write!(f, "left"),
+            Action::Right => write!(f, "right"),
+            Action::Set(digit) => write!(f, "set_{}", digit),
+            Action::Clear => write!(f, "clear"),
+            Action::Undo => write!(f, "undo"),
+            Action::Redo => write!(f, "redo"),
+            Action::Rotate => write!(f, "rotate"),
+            Action::Mirror => write!(f, "mirror"),
+            Action::Transpose => write!(f, "transpose"),
+            Action::SwapRow => write!(f, "swap_row"),
+            Action::SwapBand => write!(f, "swap_band"),
+            Action::SwapStack => write!(f, "swap_stack"),
+            Action::Relabel => write!(f, "relabel"),
+            Action::CopyPuzzle => write!(f, "copy_puzzle"),
+            Action::CopySolution => write!(f, "copy_solution"),
+            Action::CopyBoth => write!(f, "copy_both"),
+            Action::Export => write!(f, "export"),
+            Action::Play => write!(f, "play"),
+            Action::Check => write!(f, "check"),
+            Action::Hint => write!(f, "hint"),
+            Action::Collection => write!(f, "collection"),
+            Action::Next => write!(f, "next"),
+            Action::Previous => write!(f, "previous"),
+            Action::Log => write!(f, "log"),
+            Action::LogUp => write!(f, "log_up"),
+            Action::LogDown => write!(f, "log_down"),
+        }
+    }
+}
+
+impl FromStr for Action {
+    type Err = KeymapError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Action::all()
+            .find(|action| action.to_string() == s)
+            .ok_or_else(|| KeymapError::UnknownAction(s.to_string()))
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "ctrl-"),
+            (KeyModifiers::ALT, "alt-"),
+            (KeyModifiers::SHIFT, "shift-"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{}", name)?;
+            }
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "f{}", n),
+            KeyCode::PageUp => write!(f, "pageup"),
+            KeyCode::PageDown => write!(f, "pagedown"),
+            KeyCode::Backspace => write!(f, "backspace"),
+            code => write!(f, "{}", format!("{:?}", code).to_lowercase()),
+        }
+    }
+}
+
+impl FromStr for Key {
+    type Err = KeymapError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || KeymapError::UnknownKey(s.to_string());
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = s;
+        while let Some((modifier, tail)) = rest.split_once('-').filter(|(_, tail)| !tail.is_empty())
+        {
+            modifiers |= match modifier.to_lowercase().as_str() {
+                "ctrl" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(err()),
+            };
+            rest = tail;
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            // a shifted key arrives as its character, `M` rather than `shift-m`
+            (Some(_), None) if modifiers.contains(KeyModifiers::SHIFT) => {
+                return Err(KeymapError::Shifted(s.to_string()))
+            }
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match rest.to_lowercase().as_str() {
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" => KeyCode::Insert,
+                "tab" => KeyCode::Tab,
+                "space" => KeyCode::Char(' '),
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                f => match f.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                    Some(n @ 1..=12) => KeyCode::F(n),
+                    _ => return Err(err()),
+                },
+            },
+        };
+        Ok(Key::new(code, modifiers))
+    }
+}
+
+impl<'de> Deserialize<'de> for Key {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn press(c: char) -> KeyEvent {
+        KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_presets() {
+        for preset in [Preset::Default, Preset::Vim, Preset::Wasd] {
+            Keymap::preset(preset).check_conflicts().unwrap();
+        }
+        let vim = Keymap::preset(Preset::Vim);
+        assert_eq!(vim.action(&press('j')), Some(Action::Down));
+        assert_eq!(vim.action(&press('7')), Some(Action::Set(7)));
+        let wasd = Keymap::preset(Preset::Wasd);
+        assert_eq!(wasd.action(&press('u')), Some(Action::Set(7)));
+        assert_eq!(wasd.action(&press('q')), None);
+    }
+
+    #[test]
+    fn test_overrides() {
+        let raw: RawKeymap = toml::from_str("preset = \"vim\"\nundo = [\"ctrl-z\"]").unwrap();
+        let keymap = Keymap::try_from(raw).unwrap();
+        let ctrl_z = KeyEvent::new(KeyCode::Char('z'), KeyModifiers::CONTROL);
+        assert_eq!(keymap.action(&ctrl_z), Some(Action::Undo));
+        assert_eq!(keymap.action(&press('u')), None);
+
+        let raw: RawKeymap = toml::from_str("preset = \"vim\"\nset_1 = [\"j\"]").unwrap();
+        let err = Keymap::try_from(raw).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "key `j` is bound to both `down` and `set_1`"
+        );
+
+        // both match the same press, as case doesn't count with ctrl
+        let raw: RawKeymap = toml::from_str("solve = [\"ctrl-C\"]").unwrap();
+        let err = Keymap::try_from(raw).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "key `ctrl-C` is bound to both `quit` and `solve`"
+        );
+
+        let raw: RawKeymap = toml::from_str("jump = [\"j\"]").unwrap();
+        assert!(Keymap::try_from(raw).is_err());
+    }
+
+    #[test]
+    fn test_shift() {
+        let shifted = KeyEvent::new(KeyCode::Char('M'), KeyModifiers::SHIFT);
+        assert!("M".parse::<Key>().unwrap().matches(&shifted));
+        let ctrl_shifted = KeyEvent::new(
+            KeyCode::Char('M'),
+            KeyModifiers::CONTROL | KeyModifiers::SHIFT,
+        );
+        assert!("ctrl-M".parse::<Key>().unwrap().matches(&ctrl_shifted));
+        assert!("shift-up"
+            .parse::<Key>()
+            .unwrap()
+            .matches(&KeyEvent::new(KeyCode::Up, KeyModifiers::SHIFT)));
+
+        for key in ["shift-m", "ctrl-shift-m", "shift-1"] {
+            assert!(
+                matches!(key.parse::<Key>(), Err(KeymapError::Shifted(_))),
+                "{}",
+                key
+            );
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use std::fs;
+
+use color_eyre::{
+    eyre::{eyre, WrapErr},
+    Result,
+};
+
+use sudoku_core::{
+    canon,
+    format::{format_grid, parse_collection, parse_grid, split_line},
+};
+
+use crate::{
+    batch::read_input,
+    cli::{DedupArgs, LookupArgs},
+    config::Config,
+};
+
+/// Copies the input to stdout, leaving out every puzzle that is the same as
+/// an earlier one; what was left out is reported on stderr.
+pub fn dedup(args: &DedupArgs) -> Result<()> {
+    let input = read_input(args.input.as_deref())?;
+    let mut lines = Vec::new();
+    let mut grids = Vec::new();
+    for (n, line) in input.lines().enumerate() {
+        let Some((puzzle, _)) = split_line(line) else {
+            continue;
+        };
+        let grid = parse_grid(puzzle).wrap_err_with(|| format!("line {}", n + 1))?;
+        lines.push((n + 1, line));
+        grids.push(grid);
+    }
+    let duplicates = canon::duplicates(&grids);
+    for ((number, line), duplicate) in lines.iter().zip(&duplicates) {
+        match duplicate {
+            Some(original) => eprintln!(
+                "line {} is the same puzzle as line {}",
+                number, lines[*original].0
+            ),
+            None => println!("{}", line),
+        }
+    }
+    Ok(())
+}
+
+/// Prints every puzzle of the library that is the same as the given one.
+pub fn lookup(args: &LookupArgs, config: &Config) -> Result<()> {
+    let grid = parse_grid(&args.puzzle)?;
+    let path = args
+        .library
+        .as_ref()
+        .or(config.library.as_ref())
+        .ok_or_else(|| eyre!("no library, pass --library or set `library` in the config"))?;
+    let content = fs::read_to_string(path)
+        .wrap_err_with(|| format!("failed to read library {}", path.display()))?;
+    let entries = parse_collection(&content)
+        .wrap_err_with(|| format!("invalid library {}", path.display()))?;
+    let canonical = canon::canonical(&grid);
+    let found: Vec<usize> =
+        canon::canonical_all(&entries.iter().map(|entry| entry.grid).collect::<Vec<_>>())
+            .into_iter()
+            .enumerate()
+            .filter(|(_, other)| *other == canonical)
+            .map(|(index, _)| index)
+            .collect();
+    if found.is_empty() {
+        println!("not in {}", path.display());
+    }
+    for index in found {
+        let entry = &entries[index];
+        println!(
+            "puzzle {} of {}: {} {}",
+            index + 1,
+            path.display(),
+            format_grid(&entry.grid),
+            entry.meta
+        );
+    }
+    Ok(())
+}
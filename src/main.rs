@@ -1,38 +1,119 @@
+mod batch;
+mod bench;
+mod booklet;
+mod cli;
+mod clipboard;
+mod collection;
+mod config;
 mod event;
+mod export;
+mod fpuzzles;
+mod keymap;
+mod library;
 mod loading;
 mod model;
-mod solver;
+mod play;
+mod progress;
+mod scan;
+mod serve;
+mod session;
+mod timer;
+mod transform;
 mod tui;
 mod ui;
 
+use clap::Parser;
+use cli::{Cli, Command};
+use collection::Collection;
+use config::Config;
 use event::{Event, EventHandler};
-use model::{update_keyevent, update_tick, Model};
+use model::{update_keyevent, update_mouseevent, update_tick, Model};
 use ratatui::prelude::{CrosstermBackend, Terminal};
 use tui::Tui;
 
 fn main() -> color_eyre::Result<()> {
-    let mut model = Model::default();
+    let cli = Cli::parse();
+    let config = Config::load(cli.config.as_deref())?;
+    match &cli.command {
+        Some(Command::Batch(args)) => return batch::run(args, &config.solver),
+        Some(Command::Bench(args)) => return bench::run(args, &config.solver),
+        Some(Command::Dedup(args)) => return library::dedup(args),
+        Some(Command::Lookup(args)) => return library::lookup(args, &config),
+        Some(Command::Transform(args)) => return transform::run(args),
+        Some(Command::Export(args)) => return export::run(args),
+        Some(Command::Booklet(args)) => return booklet::run(args, &config.solver),
+        Some(Command::Import(args)) => return fpuzzles::run_import(args),
+        Some(Command::Link(args)) => return fpuzzles::run_link(args),
+        Some(Command::Scan(args)) => return scan::run(args),
+        Some(Command::Serve(args)) => return serve::run(args, &config),
+        None => {}
+    }
+    let tick_rate = config.tick_rate;
+    let mut session = session::load(cli.new)?;
+    let saved = session
+        .as_mut()
+        .and_then(|session| session.collection.take());
+    let mut model = match session {
+        Some(session) => Model::from_session(session, config),
+        None => Model::new(config),
+    };
+    let collection = match (cli.collection, saved) {
+        (Some(path), saved) => {
+            let mut collection = Collection::load(&path)?;
+            if let Some(saved) = saved {
+                collection.restore(saved);
+            }
+            Some(collection)
+        }
+        // the file may have been moved since, which is no reason to fail
+        (None, Some(saved)) => Collection::load(&saved.path).ok().map(|mut collection| {
+            collection.restore(saved);
+            collection
+        }),
+        (None, None) => None,
+    };
+    if let Some(collection) = collection {
+        model.set_collection(collection);
+    }
+    if let Some(path) = &cli.image {
+        model.review(scan::scan(path)?, path);
+    }
 
     let backend = CrosstermBackend::new(std::io::stderr());
     let terminal = Terminal::new(backend)?;
-    let events = EventHandler::new(250);
+    let events = EventHandler::new(tick_rate);
     let mut tui = Tui::new(terminal, events);
     tui.enter()?;
+    let result = run(&mut tui, &mut model);
+    let exited = tui.exit();
+    // saved even when something failed, so that the error doesn't cost the puzzle
+    session::save(&model.session())?;
+    result.and(exited)
+}
 
+/// Draws the model and handles events until the user quits.
+fn run(tui: &mut Tui, model: &mut Model) -> color_eyre::Result<()> {
     while !model.should_quit() {
         // Render the user interface.
-        tui.draw(&mut model)?;
+        tui.draw(model)?;
         // Handle events.
         match tui.events.next()? {
-            Event::Tick => update_tick(&mut model),
-            Event::Key(key_event) => update_keyevent(&mut model, key_event),
-            Event::Mouse(_) => {}
-            Event::Resize(_, _) => {}
-            Event::FocusGained => update_tick(&mut model),
-            Event::FocusLost => {}
+            Event::Tick => update_tick(model),
+            Event::Key(key_event) => update_keyevent(model, key_event),
+            Event::Mouse(mouse_event) => {
+                let area = tui.size()?;
+                if let Some(target) = ui::locate(model, area, mouse_event.column, mouse_event.row) {
+                    update_mouseevent(model, mouse_event, target)
+                }
+            }
+            Event::Resize => {}
+            Event::FocusGained => {
+                model.resume();
+                update_tick(model)
+            }
+            Event::FocusLost => model.pause(),
+            Event::Paste(text) => model.paste(&text),
         };
     }
-
-    tui.exit()?;
     Ok(())
 }
@@ -1,17 +1,92 @@
-use std::time::Duration;
+use std::{
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::{KeyEvent, MouseButton, MouseEvent, MouseEventKind};
+use serde::{Deserialize, Serialize};
+use sudoku_core::{
+    logic,
+    solver::{SolverError, SolverHandler, SolverMessage},
+    stats::Stats,
+    Grid,
+};
 
-use crate::{loading::LoadingIcon, solver::SolverHandler};
+use crate::{
+    clipboard::{self, Copied},
+    collection::{Collection, Status},
+    config::Config,
+    export::{self, Sheet},
+    fpuzzles,
+    keymap::Action,
+    loading::LoadingIcon,
+    play::Play,
+    progress::{Progress, ProgressParser},
+    scan::Scan,
+    session::Session,
+    timer::Timer,
+    transform::Transform,
+};
+
+/// Lines scrolled by one page of the log.
+const LOG_PAGE: isize = 10;
 
 #[derive(Debug)]
 pub struct Model {
-    puzzel: [[Option<u8>; 9]; 9],
+    puzzel: Grid,
+    /// Pencil marks per cell, bit `k` set for candidate `k`.
+    marks: [[u16; 9]; 9],
+    solution: Option<Grid>,
+    result: Option<SolveState>,
     state: RunningState,
     pos: Position,
     icon: LoadingIcon,
     solver: Option<SolverHandler>,
     time: Option<Duration>,
+    /// Time spent on the current puzzle, including earlier sittings.
+    timer: Timer,
+    config: Config,
+    undo: Vec<Change>,
+    redo: Vec<Change>,
+    /// Output of the last solver run.
+    log: Vec<String>,
+    log_scroll: usize,
+    show_log: bool,
+    progress: Option<Progress>,
+    progress_parser: ProgressParser,
+    stats: Option<Stats>,
+    /// Set while the puzzle is being played by hand.
+    play: Option<Play>,
+    collection: Option<Collection>,
+    show_collection: bool,
+    /// Shown in the status bar until the next key press.
+    message: Option<Message>,
+    /// A scanned puzzle, until it is confirmed.
+    review: Option<Review>,
+}
+
+/// A puzzle read from an image that still has to be checked.
+#[derive(Debug, Clone, Copy)]
+struct Review {
+    /// Cells whose digit is a guess.
+    unsure: [[bool; 9]; 9],
+    /// Changes on the undo history from before the scan.
+    depth: usize,
+}
+
+/// A line for the status bar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    Info(String),
+    Error(String),
+}
+
+/// An entry of the undo history.
+#[derive(Debug, Clone, Copy)]
+enum Change {
+    /// A cell was changed, with the puzzle from before.
+    Edit(Grid),
+    Transform(Transform),
 }
 
 #[derive(Debug, Default, PartialEq, Eq)]
@@ -20,37 +95,112 @@ pub enum RunningState {
     Presolve,
     Solving,
     Done(SolveState),
+    /// The solver could not be run, with the reason.
+    Failed(String),
     Leaving,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SolveState {
     Solved,
     Infeasible,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Position {
+    /// A cell of the grid, as (row, column).
     Left(usize, usize),
     RightUp,
     RightDown,
 }
 
+/// What a mouse event points at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Target {
+    pub pos: Position,
+    /// Candidate under the pointer when it is on a cell.
+    pub candidate: u8,
+}
+
 impl Model {
-    pub fn default() -> Self {
+    pub fn new(config: Config) -> Self {
         let puzzel = [[None; 9]; 9];
         Model {
-            puzzel: puzzel,
+            puzzel,
+            marks: [[0; 9]; 9],
+            solution: None,
+            result: None,
             state: RunningState::Presolve,
             pos: Position::default(),
             icon: LoadingIcon::default(),
             solver: None,
             time: None,
+            timer: Timer::default(),
+            config,
+            undo: Vec::new(),
+            redo: Vec::new(),
+            log: Vec::new(),
+            log_scroll: 0,
+            show_log: false,
+            progress: None,
+            progress_parser: ProgressParser::default(),
+            stats: None,
+            play: None,
+            collection: None,
+            show_collection: false,
+            message: None,
+            review: None,
+        }
+    }
+
+    /// Restores a model from a session saved in an earlier sitting.
+    pub fn from_session(session: Session, config: Config) -> Self {
+        let play = session.play.map(|mut play| {
+            if play.finished {
+                play.timer.pause();
+            }
+            play.start_solver(&config.solver);
+            play
+        });
+        Model {
+            puzzel: session.puzzel,
+            marks: session.marks,
+            solution: session.solution,
+            result: session.result,
+            state: session
+                .result
+                .map_or(RunningState::Presolve, RunningState::Done),
+            pos: session.pos,
+            time: session.time,
+            timer: Timer::new(session.elapsed),
+            play,
+            ..Model::new(config)
+        }
+    }
+
+    /// Snapshot of everything worth keeping across sittings.
+    pub fn session(&self) -> Session {
+        Session {
+            puzzel: self.puzzel,
+            marks: self.marks,
+            solution: self.solution,
+            result: self.result,
+            time: self.time,
+            pos: self.pos,
+            elapsed: self.get_elapsed(),
+            play: self.play.as_ref().map(Play::saved),
+            collection: self.collection.as_ref().map(Collection::state),
         }
     }
 
     pub fn get_number(&self, i: usize, j: usize) -> String {
-        self.puzzel[i][j].map_or("".into(), |x| x.to_string())
+        self.puzzel[i][j]
+            .or_else(|| self.solution.and_then(|solution| solution[i][j]))
+            .map_or("".into(), |x| x.to_string())
+    }
+
+    pub fn get_marks(&self, i: usize, j: usize) -> u16 {
+        self.marks[i][j]
     }
 
     pub fn get_position(&self) -> &Position {
@@ -65,6 +215,10 @@ impl Model {
         &self.state
     }
 
+    pub fn get_config(&self) -> &Config {
+        &self.config
+    }
+
     pub fn get_icon(&self) -> &LoadingIcon {
         &self.icon
     }
@@ -76,10 +230,538 @@ impl Model {
         unimplemented!()
     }
 
-    pub fn get_puzzel(&self) -> [[Option<u8>; 9]; 9] {
+    /// Time spent on the current puzzle, including earlier sittings.
+    pub fn get_elapsed(&self) -> Duration {
+        self.timer.elapsed()
+    }
+
+    pub fn get_play(&self) -> Option<&Play> {
+        self.play.as_ref()
+    }
+
+    pub fn get_collection(&self) -> Option<&Collection> {
+        self.collection.as_ref()
+    }
+
+    pub fn show_collection(&self) -> bool {
+        self.show_collection
+    }
+
+    /// Works through a collection, showing its list unless a puzzle of it is
+    /// already on the board.
+    pub fn set_collection(&mut self, collection: Collection) {
+        self.show_collection = collection.current.is_none();
+        self.collection = Some(collection);
+    }
+
+    /// Replaces the board with a puzzle of the collection.
+    fn open(&mut self, index: usize) {
+        let Some(collection) = &mut self.collection else {
+            return;
+        };
+        let Some(entry) = collection.entries.get(index) else {
+            return;
+        };
+        collection.current = Some(index);
+        collection.selected = index;
+        self.puzzel = entry.grid;
+        self.marks = [[0; 9]; 9];
+        self.solution = None;
+        self.result = None;
+        self.state = RunningState::Presolve;
+        self.pos = Position::default();
+        self.solver = None;
+        self.time = None;
+        self.timer = Timer::default();
+        self.undo.clear();
+        self.redo.clear();
+        self.log.clear();
+        self.log_scroll = 0;
+        self.progress = None;
+        self.stats = None;
+        self.play = None;
+        self.review = None;
+        self.show_collection = false;
+    }
+
+    /// Opens the puzzle `offset` entries away from the current one.
+    fn open_next(&mut self, offset: isize) {
+        let Some(collection) = &self.collection else {
+            return;
+        };
+        let index = match collection.current {
+            Some(current) => current.checked_add_signed(offset),
+            None => Some(0),
+        };
+        if let Some(index) = index {
+            self.open(index);
+        }
+    }
+
+    /// Records how a puzzle of the collection went, if it is still unchanged.
+    fn record(&mut self, grid: Grid, status: Status) {
+        let Some(collection) = &mut self.collection else {
+            return;
+        };
+        if let Some(index) = collection.current {
+            if collection.entries[index].grid == grid {
+                collection.status[index] = status;
+            }
+        }
+    }
+
+    /// Stops the clocks, e.g. while the terminal is out of focus.
+    pub fn pause(&mut self) {
+        self.timer.pause();
+        if let Some(play) = &mut self.play {
+            play.timer.pause();
+        }
+    }
+
+    pub fn resume(&mut self) {
+        self.timer.resume();
+        if let Some(play) = self.play.as_mut().filter(|play| !play.finished) {
+            play.timer.resume();
+        }
+    }
+
+    pub fn get_log(&self) -> &[String] {
+        &self.log
+    }
+
+    /// Number of lines the log is scrolled up from its end.
+    pub fn get_log_scroll(&self) -> usize {
+        self.log_scroll
+    }
+
+    pub fn show_log(&self) -> bool {
+        self.show_log
+    }
+
+    pub fn get_progress(&self) -> Option<&Progress> {
+        self.progress.as_ref()
+    }
+
+    /// Statistics of the last solver run.
+    pub fn get_stats(&self) -> Option<&Stats> {
+        self.stats.as_ref()
+    }
+
+    pub fn get_message(&self) -> Option<&Message> {
+        self.message.as_ref()
+    }
+
+    /// Whether a scanned puzzle is waiting to be confirmed.
+    pub fn in_review(&self) -> bool {
+        self.review.is_some()
+    }
+
+    pub fn is_unsure(&self, i: usize, j: usize) -> bool {
+        self.review.is_some_and(|review| review.unsure[i][j])
+    }
+
+    /// Puts a puzzle read from an image on the board, to be checked and
+    /// confirmed before it can be solved.
+    pub fn review(&mut self, scan: Scan, path: &Path) {
+        self.play = None;
+        let depth = self.undo.len();
+        self.undo.push(Change::Edit(self.puzzel));
+        self.redo.clear();
+        self.puzzel = scan.grid;
+        self.marks = [[0; 9]; 9];
+        self.reset_result();
+        let unsure = scan
+            .unsure
+            .iter()
+            .flatten()
+            .filter(|unsure| **unsure)
+            .count();
+        self.message = Some(Message::Info(format!(
+            "Read {} digits from {}, {} highlighted as unsure; correct them and confirm with {} on the panel",
+            scan.grid.iter().flatten().flatten().count(),
+            path.display(),
+            unsure,
+            self.config
+                .keys
+                .key(Action::Solve)
+                .map_or("?".to_string(), |key| key.to_string())
+        )));
+        self.review = Some(Review {
+            unsure: scan.unsure,
+            depth,
+        });
+    }
+
+    /// Replaces the board with a pasted puzzle, as one undoable change; an
+    /// f-puzzles or SudokuPad link keeps only its givens.
+    pub fn paste(&mut self, text: &str) {
+        if self.play.is_some() || self.state == RunningState::Solving {
+            self.message = Some(Message::Error(
+                "Can't paste a puzzle while playing or solving".to_string(),
+            ));
+            return;
+        }
+        match fpuzzles::read_puzzle(text) {
+            Ok(fpuzzles::Import {
+                grid: puzzel,
+                dropped,
+                ..
+            }) => {
+                self.undo.push(Change::Edit(self.puzzel));
+                self.redo.clear();
+                self.puzzel = puzzel;
+                self.marks = [[0; 9]; 9];
+                self.reset_result();
+                self.review = None;
+                self.message = (!dropped.is_empty())
+                    .then(|| Message::Info(format!("Dropped {}", fpuzzles::describe(&dropped))));
+            }
+            Err(err) => {
+                self.message = Some(Message::Error(format!(
+                    "Pasted text is not a puzzle: {}",
+                    err
+                )))
+            }
+        }
+    }
+
+    /// Copies the puzzle, the solution or both in the configured format.
+    fn copy(&mut self, action: Action) {
+        // while playing, the puzzle is what was given
+        let puzzel = self.play.as_ref().map_or(self.puzzel, |play| play.givens);
+        let format = self.config.clipboard.format;
+        let separator = if format == clipboard::Format::Line {
+            "\n"
+        } else {
+            "\n\n"
+        };
+        let (what, text) = match (action, self.solution) {
+            (Action::CopyPuzzle, _) => ("puzzle", format.grid(&puzzel)),
+            (Action::CopySolution, Some(solution)) => ("solution", format.grid(&solution)),
+            (_, Some(solution)) => (
+                "puzzle and solution",
+                format.grid(&puzzel) + separator + &format.grid(&solution),
+            ),
+            (_, None) => {
+                self.message = Some(Message::Error("No solution to copy yet".to_string()));
+                return;
+            }
+        };
+        self.message = Some(match clipboard::copy(&text, &self.config.clipboard) {
+            Ok(Copied::Clipboard) => Message::Info(format!("Copied the {}", what)),
+            Ok(Copied::File(path)) => {
+                Message::Info(format!("Wrote the {} to {}", what, path.display()))
+            }
+            Err(err) => Message::Error(format!("Failed to copy the {}: {}", what, err)),
+        });
+    }
+
+    /// Writes the board to a printable page, named after the current time.
+    fn export(&mut self) {
+        let config = &self.config.export;
+        let (title, meta) = match &self.collection {
+            Some(Collection {
+                current: Some(current),
+                entries,
+                ..
+            }) => (
+                format!("Puzzle {}", current + 1),
+                entries[*current].meta.clone(),
+            ),
+            _ => ("Sudoku".to_string(), String::new()),
+        };
+        let sheet = Sheet {
+            title,
+            meta,
+            puzzel: self.play.as_ref().map_or(self.puzzel, |play| play.givens),
+            solution: self.solution.filter(|_| config.solution),
+        };
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs());
+        let path = config.dir.clone().unwrap_or_default().join(format!(
+            "sudoku-{}.{}",
+            seconds,
+            config.format.extension()
+        ));
+        self.message = Some(match export::write(&path, &sheet) {
+            Ok(()) => Message::Info(format!("Exported to {}", path.display())),
+            Err(err) => Message::Error(format!("Export failed: {}", err)),
+        });
+    }
+
+    fn scroll_log(&mut self, lines: isize) {
+        let max = self.log.len().saturating_sub(1);
+        self.log_scroll = self.log_scroll.saturating_add_signed(lines).min(max);
+    }
+
+    pub fn get_puzzel(&self) -> Grid {
         self.puzzel
     }
 
+    /// Changes a cell of the puzzle, discarding a stale solve result.
+    fn set_cell(&mut self, x: usize, y: usize, value: Option<u8>) {
+        if self.puzzel[x][y] == value || self.is_locked(x, y) {
+            return;
+        }
+        self.undo.push(Change::Edit(self.puzzel));
+        self.redo.clear();
+        self.puzzel[x][y] = value;
+        if let Some(review) = &mut self.review {
+            review.unsure[x][y] = false;
+        }
+        self.reset_result();
+        self.check_finished();
+    }
+
+    /// Reverts the last change to the puzzle.
+    fn undo(&mut self) {
+        if let Some(change) = self.take_change(true) {
+            let redo = self.revert(change, true);
+            self.redo.push(redo);
+        }
+    }
+
+    /// Reapplies the last change reverted by [`Model::undo`].
+    fn redo(&mut self) {
+        if let Some(change) = self.take_change(false) {
+            let undo = self.revert(change, false);
+            self.undo.push(undo);
+        }
+    }
+
+    /// Pops the next change off the undo or redo history, if it can be
+    /// reverted now.
+    fn take_change(&mut self, undo: bool) -> Option<Change> {
+        // the solution on its way belongs to the board as it is
+        if self.state == RunningState::Solving
+            || self.play.as_ref().is_some_and(|play| play.finished)
+        {
+            return None;
+        }
+        let can_transform = self.can_transform();
+        let history = if undo { &mut self.undo } else { &mut self.redo };
+        match history.last() {
+            Some(Change::Transform(_)) if !can_transform => None,
+            _ => history.pop(),
+        }
+    }
+
+    /// Undoes or redoes a change, returning the change that reverts it again.
+    fn revert(&mut self, change: Change, undo: bool) -> Change {
+        match change {
+            Change::Edit(puzzel) => {
+                let previous = std::mem::replace(&mut self.puzzel, puzzel);
+                // undone past the scan, the board is no longer the scanned one
+                if undo
+                    && self
+                        .review
+                        .is_some_and(|review| self.undo.len() <= review.depth)
+                {
+                    self.review = None;
+                }
+                self.reset_result();
+                self.check_finished();
+                Change::Edit(previous)
+            }
+            Change::Transform(transform) => {
+                self.apply(if undo { transform.inverse() } else { transform });
+                Change::Transform(transform)
+            }
+        }
+    }
+
+    /// Whether the board may be transformed: a running solver or a game
+    /// would be left with the old grid.
+    fn can_transform(&self) -> bool {
+        self.play.is_none() && self.state != RunningState::Solving
+    }
+
+    /// Moves everything on the board, solution included, the same way.
+    fn apply(&mut self, transform: Transform) {
+        self.puzzel = transform.grid(&self.puzzel);
+        self.marks = transform.marks(&self.marks);
+        self.solution = self.solution.map(|solution| transform.grid(&solution));
+        if let Some(review) = &mut self.review {
+            let mut unsure = [[false; 9]; 9];
+            for (i, row) in review.unsure.iter().enumerate() {
+                for (j, flag) in row.iter().enumerate() {
+                    let (x, y) = transform.cell(i, j);
+                    unsure[x][y] = *flag;
+                }
+            }
+            review.unsure = unsure;
+        }
+        if let Position::Left(row, column) = self.pos {
+            let (row, column) = transform.cell(row, column);
+            self.pos = Position::Left(row, column);
+        }
+    }
+
+    /// Transforms the board as an undoable change.
+    fn transform(&mut self, transform: Transform) {
+        if !self.can_transform() {
+            return;
+        }
+        self.apply(transform);
+        self.undo.push(Change::Transform(transform));
+        self.redo.clear();
+    }
+
+    /// The row, band or stack of the selected cell swapped with the next one.
+    fn swap_selected(&mut self, action: Action) {
+        let (row, column) = match self.pos {
+            Position::Left(row, column) => (row, column),
+            _ => (0, 0),
+        };
+        let transform = match action {
+            Action::SwapRow => Transform::SwapRows(row, row / 3 * 3 + (row + 1) % 3),
+            Action::SwapBand => Transform::SwapBands(row / 3, (row / 3 + 1) % 3),
+            _ => Transform::SwapStacks(column / 3, (column / 3 + 1) % 3),
+        };
+        self.transform(transform);
+    }
+
+    /// Whether a cell can't be changed, because it is given, play is over or
+    /// the solver is still working on the board.
+    fn is_locked(&self, x: usize, y: usize) -> bool {
+        self.state == RunningState::Solving
+            || self
+                .play
+                .as_ref()
+                .is_some_and(|play| play.finished || play.is_given(x, y))
+    }
+
+    /// Starts playing the digits on the board, or stops playing.
+    fn toggle_play(&mut self) {
+        if self.play.take().is_some() || self.state == RunningState::Solving {
+            return;
+        }
+        if self.puzzel.iter().flatten().all(Option::is_none) {
+            return;
+        }
+        if self.review.is_some() {
+            self.message = Some(Message::Error(
+                "Confirm the scanned puzzle before playing it".to_string(),
+            ));
+            return;
+        }
+        // a solve result would give the answers away
+        self.state = RunningState::Presolve;
+        self.solution = None;
+        self.result = None;
+        self.time = None;
+        // earlier snapshots have different givens
+        self.undo.clear();
+        self.redo.clear();
+        self.play = Some(Play::new(self.puzzel, &self.config.solver));
+    }
+
+    fn toggle_check(&mut self) {
+        if let Some(play) = &mut self.play {
+            play.check = !play.check;
+        }
+    }
+
+    /// Points out a wrong or clashing entry, or fills in the next logical
+    /// deduction, or reveals a cell from the solution when logic alone is
+    /// stuck.
+    fn hint(&mut self) {
+        let Some(play) = self.play.as_ref().filter(|play| !play.finished) else {
+            return;
+        };
+        let (row, column, message) = if let Some((row, column)) = play.first_wrong(&self.puzzel) {
+            (row, column, format!("R{}C{} is wrong", row + 1, column + 1))
+        } else if let Some((row, column, err)) = play.first_conflict(&self.puzzel) {
+            // logic would reason from the mistake
+            (
+                row,
+                column,
+                format!("R{}C{} clashes, {}", row + 1, column + 1, err),
+            )
+        } else if let Some(step) = logic::next_step(&self.puzzel) {
+            self.set_cell(step.row, step.column, Some(step.digit));
+            (step.row, step.column, step.to_string())
+        } else if let Some(solution) = play.solution {
+            let selected = match self.pos {
+                Position::Left(row, column) if self.puzzel[row][column].is_none() => {
+                    Some((row, column))
+                }
+                _ => None,
+            };
+            let Some((row, column)) = selected.or_else(|| {
+                (0..9)
+                    .flat_map(|i| (0..9).map(move |j| (i, j)))
+                    .find(|&(i, j)| self.puzzel[i][j].is_none())
+            }) else {
+                return;
+            };
+            let digit = solution[row][column].unwrap_or_default();
+            self.set_cell(row, column, Some(digit));
+            let message = format!("R{}C{} = {}, from the solution", row + 1, column + 1, digit);
+            (row, column, message)
+        } else {
+            if let Some(play) = &mut self.play {
+                play.message = Some("No hint yet, the solution is being computed".to_string());
+            }
+            return;
+        };
+        self.pos = Position::Left(row, column);
+        if let Some(play) = &mut self.play {
+            play.hints += 1;
+            play.message = Some(message);
+        }
+    }
+
+    /// Stops the timer once every cell is filled in without conflicts.
+    fn check_finished(&mut self) {
+        if let Some(play) = &mut self.play {
+            if logic::is_solved(&self.puzzel) {
+                play.finished = true;
+                play.timer.pause();
+                play.message = None;
+                let (givens, time) = (play.givens, play.timer.elapsed());
+                self.record(givens, Status::Solved(time));
+            }
+        }
+    }
+
+    fn toggle_mark(&mut self, x: usize, y: usize, k: u8) {
+        self.marks[x][y] ^= 1 << k;
+    }
+
+    /// Starts solving the current puzzle in the background.
+    fn solve(&mut self) {
+        if self.play.is_some() {
+            return;
+        }
+        // the first press confirms a scanned puzzle
+        if self.review.take().is_some() {
+            self.message = Some(Message::Info("Scanned puzzle confirmed".to_string()));
+            return;
+        }
+        self.pos = Position::RightDown;
+        self.state = RunningState::Solving;
+        self.log.clear();
+        self.log_scroll = 0;
+        self.progress = None;
+        self.progress_parser = ProgressParser::default();
+        self.stats = None;
+        self.solver = Some(SolverHandler::new(
+            self.get_puzzel(),
+            self.config.solver.clone(),
+        ));
+    }
+
+    fn reset_result(&mut self) {
+        if let RunningState::Done(_) | RunningState::Failed(_) = self.state {
+            self.state = RunningState::Presolve;
+            self.solution = None;
+            self.result = None;
+            self.time = None;
+        }
+    }
+
     pub fn quit(&mut self) {
         self.state = RunningState::Leaving;
     }
@@ -99,73 +781,243 @@ impl Position {
 }
 
 pub fn update_keyevent(model: &mut Model, key_event: KeyEvent) {
-    match key_event.code {
-        KeyCode::Esc | KeyCode::Char('q') => model.quit(),
-        KeyCode::Char('c') | KeyCode::Char('C') => {
-            if key_event.modifiers == KeyModifiers::CONTROL {
-                model.quit()
+    model.message = None;
+    let Some(action) = model.config.keys.action(&key_event) else {
+        return;
+    };
+    // the collection list is drawn over the grid
+    if let (true, Some(collection)) = (model.show_collection, &mut model.collection) {
+        match action {
+            Action::Quit => model.quit(),
+            Action::Up => collection.select(-1),
+            Action::Down => collection.select(1),
+            Action::LogUp => collection.select(-LOG_PAGE),
+            Action::LogDown => collection.select(LOG_PAGE),
+            Action::Solve => {
+                let selected = collection.selected;
+                model.open(selected)
             }
+            Action::Collection => model.show_collection = false,
+            _ => {}
         }
-        KeyCode::Char(c) => {
-            if c >= '1' && c <= '9' {
-                if let Position::Left(x, y) = model.get_position() {
-                    model.puzzel[*x][*y] = Some(c.to_digit(10).unwrap() as u8);
-                }
+        return;
+    }
+    match action {
+        Action::Quit => model.quit(),
+        Action::Set(digit) => {
+            if let Position::Left(x, y) = *model.get_position() {
+                model.set_cell(x, y, Some(digit));
             }
         }
-        KeyCode::Backspace => {
-            if let Position::Left(x, y) = model.get_position() {
-                model.puzzel[*x][*y] = None;
+        Action::Clear => {
+            if let Position::Left(x, y) = *model.get_position() {
+                model.set_cell(x, y, None);
             }
         }
-        KeyCode::Up => {
-            if let Position::Left(_, y) = model.get_position_mut() {
-                *y = (*y + 8) % 9;
+        Action::Undo => model.undo(),
+        Action::Redo => model.redo(),
+        Action::Rotate => model.transform(Transform::Rotate(1)),
+        Action::Mirror => model.transform(Transform::Mirror),
+        Action::Transpose => model.transform(Transform::Transpose),
+        Action::SwapRow | Action::SwapBand | Action::SwapStack => model.swap_selected(action),
+        Action::Relabel => model.transform(Transform::random_relabel()),
+        Action::CopyPuzzle | Action::CopySolution | Action::CopyBoth => model.copy(action),
+        Action::Export => model.export(),
+        Action::Play => model.toggle_play(),
+        Action::Check => model.toggle_check(),
+        Action::Hint => model.hint(),
+        Action::Collection => model.show_collection = model.collection.is_some(),
+        Action::Next => model.open_next(1),
+        Action::Previous => model.open_next(-1),
+        Action::Log => model.show_log = !model.show_log,
+        Action::LogUp => model.scroll_log(LOG_PAGE),
+        Action::LogDown => model.scroll_log(-LOG_PAGE),
+        Action::Up => {
+            if let Position::Left(row, _) = model.get_position_mut() {
+                *row = (*row + 8) % 9;
             }
         }
-        KeyCode::Down => {
-            if let Position::Left(_, y) = model.get_position_mut() {
-                *y = (*y + 1) % 9;
+        Action::Down => {
+            if let Position::Left(row, _) = model.get_position_mut() {
+                *row = (*row + 1) % 9;
             }
         }
-        KeyCode::Left => {
-            if let Position::Left(x, _) = model.get_position_mut() {
-                *x = (*x + 8) % 9;
+        Action::Left => {
+            if let Position::Left(_, column) = model.get_position_mut() {
+                *column = (*column + 8) % 9;
             } else {
                 *model.get_position_mut() = Position::default();
             }
         }
-        KeyCode::Right => {
-            if let Position::Left(x, _) = model.get_position_mut() {
-                *x = (*x + 1) % 9;
+        Action::Right => {
+            if let Position::Left(_, column) = model.get_position_mut() {
+                *column = (*column + 1) % 9;
             } else {
                 *model.get_position_mut() = Position::default();
             }
         }
-        KeyCode::Enter => {
+        Action::Solve => {
             if let Position::Left(_, _) = model.get_position_mut() {
                 *model.get_position_mut() = Position::RightUp;
             } else if let Position::RightUp = model.get_position_mut() {
-                *model.get_position_mut() = Position::RightDown;
-                model.state = RunningState::Solving;
-                model.solver = Some(SolverHandler::new(model.get_puzzel()));
+                model.solve();
             }
         }
-        _ => {}
     };
 }
 
+pub fn update_mouseevent(model: &mut Model, mouse_event: MouseEvent, target: Target) {
+    if let (true, Some(collection), Position::Left(_, _)) =
+        (model.show_collection, &mut model.collection, target.pos)
+    {
+        match mouse_event.kind {
+            MouseEventKind::ScrollUp => collection.select(-1),
+            MouseEventKind::ScrollDown => collection.select(1),
+            _ => {}
+        }
+        return;
+    }
+    // the log is drawn over the grid
+    if let (true, Position::Left(_, _)) = (model.show_log, target.pos) {
+        match mouse_event.kind {
+            MouseEventKind::ScrollUp => model.scroll_log(3),
+            MouseEventKind::ScrollDown => model.scroll_log(-3),
+            _ => {}
+        }
+        return;
+    }
+    match (mouse_event.kind, target.pos) {
+        (MouseEventKind::Down(MouseButton::Left), Position::Left(_, _)) => model.pos = target.pos,
+        (MouseEventKind::Down(MouseButton::Left), _) if model.state != RunningState::Solving => {
+            model.solve()
+        }
+        (MouseEventKind::Down(MouseButton::Right), Position::Left(x, y)) => {
+            model.pos = target.pos;
+            model.toggle_mark(x, y, target.candidate);
+        }
+        (MouseEventKind::ScrollUp | MouseEventKind::ScrollDown, Position::Left(x, y)) => {
+            model.pos = target.pos;
+            let current = model.puzzel[x][y].unwrap_or(0);
+            let next = if let MouseEventKind::ScrollUp = mouse_event.kind {
+                (current + 1) % 10
+            } else {
+                (current + 9) % 10
+            };
+            model.set_cell(x, y, Some(next).filter(|k| *k != 0));
+        }
+        _ => {}
+    }
+}
+
 pub fn update_tick(model: &mut Model) {
     model.icon.on_tick();
-    if let Some(handler) = &model.solver {
-        if let Ok(result) = handler.try_get() {
-            if let Ok((time, result)) = result {
+    if let Some(play) = &mut model.play {
+        play.on_tick();
+    }
+    while let Some(message) = model.solver.as_ref().and_then(SolverHandler::try_get) {
+        let result = match message {
+            SolverMessage::Line(line) => {
+                if let Some(progress) = model.progress_parser.feed(&line) {
+                    model.progress = Some(progress);
+                }
+                model.log.push(line);
+                continue;
+            }
+            SolverMessage::Stats(stats) => {
+                model.stats = Some(stats);
+                continue;
+            }
+            SolverMessage::Done(result) => result,
+        };
+        model.solver = None;
+        match result {
+            Ok((time, result)) => {
                 model.time = Some(time);
-                model.puzzel = result;
+                model.solution = Some(result);
+                model.result = Some(SolveState::Solved);
                 model.state = RunningState::Done(SolveState::Solved);
-            } else {
+                model.record(model.puzzel, Status::Solved(time));
+            }
+            Err(SolverError::Infeasible) => {
+                model.result = Some(SolveState::Infeasible);
                 model.state = RunningState::Done(SolveState::Infeasible);
+                model.record(model.puzzel, Status::Infeasible);
             }
+            Err(err) => model.state = RunningState::Failed(err.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_review() {
+        let mut model = Model::new(Config::default());
+        let mut scan = Scan {
+            grid: [[None; 9]; 9],
+            unsure: [[false; 9]; 9],
+        };
+        scan.grid[0][1] = Some(7);
+        scan.unsure[0][1] = true;
+        model.review(scan, Path::new("sudoku.png"));
+        assert!(model.is_unsure(0, 1));
+
+        // the flag stays with its digit
+        model.transform(Transform::Transpose);
+        assert_eq!(model.get_puzzel()[1][0], Some(7));
+        assert!(model.is_unsure(1, 0));
+        assert!(!model.is_unsure(0, 1));
+
+        model.undo();
+        assert!(model.in_review());
+        model.undo();
+        assert!(!model.in_review());
+        assert_eq!(model.get_puzzel(), [[None; 9]; 9]);
+    }
+
+    #[test]
+    fn test_edit_while_solving() {
+        let mut config = Config::default();
+        config.solver.backend = sudoku_core::solver::Backend::Native;
+        let mut model = Model::new(config);
+        model.set_cell(0, 0, Some(1));
+        model.solve();
+        assert_eq!(model.state, RunningState::Solving);
+
+        // the board stays the one being solved
+        model.set_cell(0, 1, Some(2));
+        model.undo();
+        model.transform(Transform::Transpose);
+        assert_eq!(model.puzzel[0][0], Some(1));
+        assert_eq!(model.puzzel[0][1], None);
+
+        while model.state == RunningState::Solving {
+            update_tick(&mut model);
         }
+        assert_eq!(model.solution.unwrap()[0][0], Some(1));
+        model.set_cell(0, 1, Some(2));
+        assert_eq!(model.puzzel[0][1], Some(2));
+    }
+
+    #[test]
+    fn test_hint_conflict() {
+        let mut model = Model::new(Config::default());
+        let puzzle =
+            "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+        model.puzzel = sudoku_core::format::parse_grid(puzzle).unwrap();
+        model.toggle_play();
+        model.set_cell(0, 2, Some(5));
+
+        // before the solution is in, the clash is pointed out
+        model.hint();
+        let play = model.get_play().unwrap();
+        assert_eq!(
+            play.message.as_deref(),
+            Some("R1C3 clashes, row 1 has 5 twice")
+        );
+        assert_eq!(model.pos, Position::Left(0, 2));
+        assert_eq!(model.puzzel[0][3], None);
     }
 }
@@ -0,0 +1,118 @@
+use serde::{Deserialize, Serialize};
+
+use sudoku_core::{
+    solver::{SolverConfig, SolverError, SolverHandler, SolverMessage},
+    verify::{self, VerifyError},
+    Grid,
+};
+
+use crate::timer::Timer;
+
+/// A puzzle being filled in by hand.
+///
+/// The digits on the board when play started are locked, and the solution is
+/// computed in the background so entries can be checked and hints revealed.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Play {
+    /// Locked digits of the puzzle.
+    pub givens: Grid,
+    pub timer: Timer,
+    /// Whether wrong entries are flagged.
+    pub check: bool,
+    pub hints: u32,
+    /// Set once every cell is filled in correctly, which stops the timer.
+    pub finished: bool,
+    #[serde(skip)]
+    pub solution: Option<Grid>,
+    /// Last hint or problem to show the player.
+    #[serde(skip)]
+    pub message: Option<String>,
+    #[serde(skip)]
+    solver: Option<SolverHandler>,
+}
+
+impl Play {
+    /// Starts playing the given digits.
+    pub fn new(givens: Grid, config: &SolverConfig) -> Self {
+        let mut play = Play {
+            givens,
+            timer: Timer::default(),
+            check: false,
+            hints: 0,
+            finished: false,
+            solution: None,
+            message: None,
+            solver: None,
+        };
+        play.start_solver(config);
+        play
+    }
+
+    /// Computes the solution in the background, unless play is already over.
+    pub fn start_solver(&mut self, config: &SolverConfig) {
+        if !self.finished {
+            self.solver = Some(SolverHandler::new(self.givens, config.clone()));
+        }
+    }
+
+    /// Copy of everything that is saved with the session.
+    pub fn saved(&self) -> Self {
+        Play {
+            givens: self.givens,
+            timer: self.timer,
+            check: self.check,
+            hints: self.hints,
+            finished: self.finished,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_given(&self, i: usize, j: usize) -> bool {
+        self.givens[i][j].is_some()
+    }
+
+    /// Picks up the solution once the solver is done.
+    pub fn on_tick(&mut self) {
+        while let Some(message) = self.solver.as_ref().and_then(SolverHandler::try_get) {
+            let SolverMessage::Done(result) = message else {
+                continue;
+            };
+            self.solver = None;
+            match result {
+                Ok((_, solution)) => self.solution = Some(solution),
+                Err(SolverError::Infeasible) => {
+                    self.message = Some("This puzzle has no solution".to_string())
+                }
+                Err(err) => self.message = Some(format!("No solution to check against: {}", err)),
+            }
+        }
+    }
+
+    /// Whether an entry differs from the solution.
+    pub fn is_wrong(&self, puzzel: &Grid, i: usize, j: usize) -> bool {
+        match (puzzel[i][j], self.solution) {
+            (Some(k), Some(solution)) => !self.is_given(i, j) && solution[i][j] != Some(k),
+            _ => false,
+        }
+    }
+
+    /// The first wrong entry, as (row, column).
+    pub fn first_wrong(&self, puzzel: &Grid) -> Option<(usize, usize)> {
+        (0..9)
+            .flat_map(|i| (0..9).map(move |j| (i, j)))
+            .find(|&(i, j)| self.is_wrong(puzzel, i, j))
+    }
+
+    /// The first entry that clashes with another digit of its row, column or
+    /// box, as (row, column) with the clash; needs no solution.
+    pub fn first_conflict(&self, puzzel: &Grid) -> Option<(usize, usize, VerifyError)> {
+        verify::conflicts(puzzel).into_iter().find_map(|err| {
+            let VerifyError::Duplicate { unit, digit } = err else {
+                return None;
+            };
+            unit.cells()
+                .find(|&(i, j)| puzzel[i][j] == Some(digit) && !self.is_given(i, j))
+                .map(|(i, j)| (i, j, err))
+        })
+    }
+}
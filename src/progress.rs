@@ -0,0 +1,86 @@
+/// Latest state of the branch and bound search, as shown in SCIP's display lines.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Progress {
+    /// Solving time, e.g. `1.2s`.
+    pub time: String,
+    pub nodes: u64,
+    pub dual_bound: Option<f64>,
+    pub primal_bound: Option<f64>,
+    /// Relative gap, e.g. `0.00%` or `Inf`.
+    pub gap: String,
+}
+
+/// Reads progress out of SCIP's output, one line at a time.
+///
+/// SCIP prints a table whose columns are announced by a header line such as
+/// ` time | node  | left  |LP iter| ... |  dualbound   | primalbound  |  gap   `,
+/// so the columns are looked up by name instead of by position.
+#[derive(Debug, Default)]
+pub struct ProgressParser {
+    header: Vec<String>,
+}
+
+impl ProgressParser {
+    /// Feeds a line of output, returning the progress if it is a table row.
+    pub fn feed(&mut self, line: &str) -> Option<Progress> {
+        if !line.contains('|') {
+            return None;
+        }
+        let fields: Vec<&str> = line.split('|').map(str::trim).collect();
+        if fields.contains(&"node") && fields.contains(&"gap") {
+            self.header = fields.iter().map(|f| f.to_string()).collect();
+            return None;
+        }
+        if fields.len() != self.header.len() {
+            return None;
+        }
+        let field = |name: &str| {
+            self.header
+                .iter()
+                .position(|h| h == name)
+                .map(|i| fields[i])
+                .unwrap_or("")
+        };
+        let bound = |name| field(name).parse().ok();
+        Some(Progress {
+            // the first character marks the heuristic that found a solution, if any
+            time: field("time")
+                .rsplit(' ')
+                .next()
+                .unwrap_or_default()
+                .to_string(),
+            nodes: field("node").parse().ok()?,
+            dual_bound: bound("dualbound"),
+            primal_bound: bound("primalbound"),
+            gap: field("gap").to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_feed() {
+        let mut parser = ProgressParser::default();
+        let row = "p 0.1s|     1 |     0 |     0 |     - |  locks|   0 | 729 | 324 | 324 |   0 |  0 |   0 |   0 | 0.000000e+00 | 0.000000e+00 |   0.00%| unknown";
+        // without a header the columns are unknown
+        assert_eq!(parser.feed(row), None);
+        assert_eq!(
+            parser.feed(" time | node  | left  |LP iter|LP it/n|mem/heur|mdpt |vars |cons |rows |cuts |sepa|confs|strbr|  dualbound   | primalbound  |  gap   | compl. "),
+            None
+        );
+        assert_eq!(parser.feed("presolving:"), None);
+        assert_eq!(
+            parser.feed(row),
+            Some(Progress {
+                time: "0.1s".to_string(),
+                nodes: 1,
+                dual_bound: Some(0.0),
+                primal_bound: Some(0.0),
+                gap: "0.00%".to_string(),
+            })
+        );
+    }
+}
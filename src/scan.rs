@@ -0,0 +1,469 @@
+use std::path::Path;
+
+use color_eyre::{
+    eyre::{bail, WrapErr},
+    Result,
+};
+use image::{imageops, GrayImage};
+
+use sudoku_core::{format::format_grid, Grid};
+
+use crate::cli::ScanArgs;
+
+/// Pixels per cell of the straightened grid.
+const CELL: usize = 32;
+/// Pixels left out on every side of a cell, where the grid lines are.
+const MARGIN: usize = 4;
+/// Size of the digit templates.
+const WIDTH: usize = 12;
+const HEIGHT: usize = 16;
+/// Digits rendered from a few common fonts, each a line naming the digit
+/// and font followed by `HEIGHT` rows with `#` for ink.
+const TEMPLATES: &str = include_str!("../assets/digits.txt");
+/// Larger images are shrunk to this many pixels across first.
+const MAX_SIZE: u32 = 1200;
+/// Gray levels between ink and paper below which a cell counts as empty.
+const CONTRAST: f64 = 60.0;
+/// Distance to the best template, in template pixels, beyond which a digit
+/// is a guess; likewise when the runner-up is within `AMBIGUOUS` of it.
+const DISTANT: f64 = 40.0;
+const AMBIGUOUS: f64 = 8.0;
+
+/// A puzzle read from an image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scan {
+    pub grid: Grid,
+    /// Cells whose digit is a guess and should be checked.
+    pub unsure: [[bool; 9]; 9],
+}
+
+/// Dark pixels of an image.
+struct Mask {
+    width: usize,
+    height: usize,
+    dark: Vec<bool>,
+}
+
+type Template = (u8, [f64; WIDTH * HEIGHT]);
+
+fn templates() -> Vec<Template> {
+    let mut lines = TEMPLATES.lines();
+    let mut templates = Vec::new();
+    while let Some(header) = lines.next() {
+        let digit = header.as_bytes()[0] - b'0';
+        let mut pixels = [0.0; WIDTH * HEIGHT];
+        for row in 0..HEIGHT {
+            let line = lines.next().unwrap_or_default();
+            for (column, c) in line.chars().take(WIDTH).enumerate() {
+                pixels[row * WIDTH + column] = (c == '#') as u8 as f64;
+            }
+        }
+        templates.push((digit, pixels));
+    }
+    templates
+}
+
+/// Marks the pixels darker than their surroundings, so that shadows and
+/// uneven light don't matter.
+fn threshold(image: &GrayImage) -> Mask {
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    // sums of the pixels above and left of every point
+    let mut integral = vec![0u64; (width + 1) * (height + 1)];
+    for y in 0..height {
+        let mut row = 0;
+        for x in 0..width {
+            row += image.get_pixel(x as u32, y as u32)[0] as u64;
+            integral[(y + 1) * (width + 1) + x + 1] = integral[y * (width + 1) + x + 1] + row;
+        }
+    }
+    let radius = (width.max(height) / 32).max(2);
+    let mut dark = vec![false; width * height];
+    for y in 0..height {
+        let (top, bottom) = (y.saturating_sub(radius), (y + radius + 1).min(height));
+        for x in 0..width {
+            let (left, right) = (x.saturating_sub(radius), (x + radius + 1).min(width));
+            let sum = integral[bottom * (width + 1) + right] + integral[top * (width + 1) + left]
+                - integral[top * (width + 1) + right]
+                - integral[bottom * (width + 1) + left];
+            let count = ((bottom - top) * (right - left)) as u64;
+            let pixel = image.get_pixel(x as u32, y as u32)[0] as u64;
+            dark[y * width + x] = pixel * count * 100 < sum * 85;
+        }
+    }
+    Mask {
+        width,
+        height,
+        dark,
+    }
+}
+
+/// The groups of touching dark pixels, as lists of `(x, y)`.
+fn components(mask: &Mask) -> Vec<Vec<(usize, usize)>> {
+    let mut seen = vec![false; mask.dark.len()];
+    let mut components = Vec::new();
+    for start in 0..mask.dark.len() {
+        if !mask.dark[start] || seen[start] {
+            continue;
+        }
+        seen[start] = true;
+        let mut stack = vec![start];
+        let mut pixels = Vec::new();
+        while let Some(i) = stack.pop() {
+            let (x, y) = (i % mask.width, i / mask.width);
+            pixels.push((x, y));
+            for ny in y.saturating_sub(1)..(y + 2).min(mask.height) {
+                for nx in x.saturating_sub(1)..(x + 2).min(mask.width) {
+                    let n = ny * mask.width + nx;
+                    if mask.dark[n] && !seen[n] {
+                        seen[n] = true;
+                        stack.push(n);
+                    }
+                }
+            }
+        }
+        components.push(pixels);
+    }
+    components
+}
+
+/// Smallest and largest x and y.
+fn bounds(pixels: &[(usize, usize)]) -> (usize, usize, usize, usize) {
+    pixels.iter().fold(
+        (usize::MAX, usize::MAX, 0, 0),
+        |(left, top, right, bottom), &(x, y)| {
+            (left.min(x), top.min(y), right.max(x), bottom.max(y))
+        },
+    )
+}
+
+/// The projective map taking a square of side `size` to the corners,
+/// clockwise from the top left.
+fn homography(size: f64, corners: [(f64, f64); 4]) -> [f64; 8] {
+    let square = [(0.0, 0.0), (size, 0.0), (size, size), (0.0, size)];
+    let mut a = [[0.0; 9]; 8];
+    for (i, (&(u, v), &(x, y))) in square.iter().zip(&corners).enumerate() {
+        a[2 * i] = [u, v, 1.0, 0.0, 0.0, 0.0, -u * x, -v * x, x];
+        a[2 * i + 1] = [0.0, 0.0, 0.0, u, v, 1.0, -u * y, -v * y, y];
+    }
+    for column in 0..8 {
+        let pivot = (column..8)
+            .max_by(|&i, &j| a[i][column].abs().total_cmp(&a[j][column].abs()))
+            .unwrap_or(column);
+        a.swap(column, pivot);
+        let pivot = a[column];
+        for (row, equation) in a.iter_mut().enumerate() {
+            if row != column {
+                let factor = equation[column] / pivot[column];
+                for (x, p) in equation.iter_mut().zip(pivot).skip(column) {
+                    *x -= factor * p;
+                }
+            }
+        }
+    }
+    std::array::from_fn(|i| a[i][8] / a[i][i])
+}
+
+fn project(h: &[f64; 8], (u, v): (f64, f64)) -> (f64, f64) {
+    let w = h[6] * u + h[7] * v + 1.0;
+    (
+        (h[0] * u + h[1] * v + h[2]) / w,
+        (h[3] * u + h[4] * v + h[5]) / w,
+    )
+}
+
+/// Finds the outline of the grid, the largest group of dark pixels, and
+/// straightens it into a square of nine cells a side.
+fn straighten(image: &GrayImage) -> Result<Vec<u8>> {
+    let mask = threshold(image);
+    let grid = components(&mask)
+        .into_iter()
+        .max_by_key(|pixels| {
+            let (left, top, right, bottom) = bounds(pixels);
+            (right - left) * (bottom - top)
+        })
+        .unwrap_or_default();
+    let (left, top, right, bottom) = bounds(&grid);
+    let (width, height) = (right.saturating_sub(left), bottom.saturating_sub(top));
+    if width.min(height) < 9 * 6 || width > 2 * height || height > 2 * width {
+        bail!("no sudoku grid found");
+    }
+    let corner = |score: fn(f64, f64) -> f64| {
+        grid.iter()
+            .map(|&(x, y)| (x as f64, y as f64))
+            .max_by(|a, b| score(a.0, a.1).total_cmp(&score(b.0, b.1)))
+            .unwrap_or_default()
+    };
+    let corners = [
+        corner(|x, y| -x - y),
+        corner(|x, y| x - y),
+        corner(|x, y| x + y),
+        corner(|x, y| y - x),
+    ];
+    let size = 9 * CELL;
+    let h = homography(size as f64, corners);
+    if h.iter().any(|x| !x.is_finite()) {
+        bail!("no sudoku grid found");
+    }
+    let sample = |x: f64, y: f64| {
+        let (x0, y0) = (x.floor(), y.floor());
+        let pixel = |x: f64, y: f64| {
+            if x < 0.0 || y < 0.0 || x >= image.width() as f64 || y >= image.height() as f64 {
+                255.0
+            } else {
+                image.get_pixel(x as u32, y as u32)[0] as f64
+            }
+        };
+        let (fx, fy) = (x - x0, y - y0);
+        (pixel(x0, y0) * (1.0 - fx) + pixel(x0 + 1.0, y0) * fx) * (1.0 - fy)
+            + (pixel(x0, y0 + 1.0) * (1.0 - fx) + pixel(x0 + 1.0, y0 + 1.0) * fx) * fy
+    };
+    let mut square = vec![0; size * size];
+    for v in 0..size {
+        for u in 0..size {
+            let (x, y) = project(&h, (u as f64 + 0.5, v as f64 + 0.5));
+            square[v * size + u] = sample(x - 0.5, y - 0.5) as u8;
+        }
+    }
+    Ok(square)
+}
+
+/// The gray level that best splits the pixels into ink and paper, with the
+/// mean of each side.
+fn otsu(pixels: &[u8]) -> (u8, f64, f64) {
+    let mut histogram = [0usize; 256];
+    for &p in pixels {
+        histogram[p as usize] += 1;
+    }
+    let total: f64 = pixels.iter().map(|&p| p as f64).sum();
+    let (mut count, mut sum) = (0.0, 0.0);
+    let mut best = (0, 0.0, 0.0, 0.0);
+    for (level, &n) in histogram.iter().enumerate() {
+        count += n as f64;
+        sum += (level * n) as f64;
+        let rest = pixels.len() as f64 - count;
+        if count == 0.0 || rest == 0.0 {
+            continue;
+        }
+        let (dark, light) = (sum / count, (total - sum) / rest);
+        let spread = count * rest * (dark - light).powi(2);
+        if spread > best.3 {
+            best = (level as u8, dark, light, spread);
+        }
+    }
+    (best.0, best.1, best.2)
+}
+
+/// The ink of a cell near its middle, scaled into the template size the
+/// way the templates were, or `None` for an empty cell.
+fn digit_image(square: &[u8], row: usize, column: usize) -> Option<[f64; WIDTH * HEIGHT]> {
+    let side = CELL - 2 * MARGIN;
+    let (x0, y0) = (column * CELL + MARGIN, row * CELL + MARGIN);
+    let pixels: Vec<u8> = (0..side * side)
+        .map(|i| square[(y0 + i / side) * 9 * CELL + x0 + i % side])
+        .collect();
+    let (level, dark, light) = otsu(&pixels);
+    if light - dark < CONTRAST {
+        return None;
+    }
+    let mask = Mask {
+        width: side,
+        height: side,
+        dark: pixels.iter().map(|&p| p <= level).collect(),
+    };
+    // what is left of the grid lines is thin and runs into the edges, away
+    // from the middle
+    let middle = (CELL * 3 / 8 - MARGIN)..(CELL * 5 / 8 - MARGIN);
+    let ink: Vec<(usize, usize)> = components(&mask)
+        .into_iter()
+        .filter(|pixels| {
+            let (left, top, right, bottom) = bounds(pixels);
+            let edge = left == 0 || top == 0 || right == side - 1 || bottom == side - 1;
+            let thin = (right - left).min(bottom - top) < side / 6;
+            pixels.len() >= side * side / 100
+                && !(edge && thin)
+                && left < middle.end
+                && right >= middle.start
+                && top < middle.end
+                && bottom >= middle.start
+        })
+        .flatten()
+        .collect();
+    let (left, top, right, bottom) = bounds(&ink);
+    if ink.is_empty() || bottom - top + 1 < CELL * 3 / 10 {
+        return None;
+    }
+    let (width, height) = (right - left + 1, bottom - top + 1);
+    let mut cropped = vec![0.0; width * height];
+    for (x, y) in ink {
+        cropped[(y - top) * width + x - left] = 1.0;
+    }
+    let scale = (WIDTH as f64 / width as f64).min(HEIGHT as f64 / height as f64);
+    let offset = (
+        (WIDTH as f64 - width as f64 * scale) / 2.0,
+        (HEIGHT as f64 - height as f64 * scale) / 2.0,
+    );
+    let mut scaled = [0.0; WIDTH * HEIGHT];
+    for (i, value) in scaled.iter_mut().enumerate() {
+        let (tx, ty) = ((i % WIDTH) as f64, (i / WIDTH) as f64);
+        let from = |t: f64, offset: f64, limit: usize| {
+            let start = ((t - offset) / scale).max(0.0);
+            let end = ((t + 1.0 - offset) / scale).min(limit as f64);
+            start as usize..(end.ceil() as usize).min(limit)
+        };
+        let (xs, ys) = (from(tx, offset.0, width), from(ty, offset.1, height));
+        let cells = xs.len() * ys.len();
+        if cells > 0 {
+            let sum: f64 = ys
+                .flat_map(|y| xs.clone().map(move |x| (x, y)))
+                .map(|(x, y)| cropped[y * width + x])
+                .sum();
+            *value = sum / cells as f64;
+        }
+    }
+    Some(scaled)
+}
+
+/// The digit whose template is closest, and whether the next best digit
+/// comes close.
+fn classify(image: &[f64; WIDTH * HEIGHT], templates: &[Template]) -> (u8, bool) {
+    let mut distances = [f64::MAX; 10];
+    for (digit, template) in templates {
+        let distance: f64 = image.iter().zip(template).map(|(a, b)| (a - b).abs()).sum();
+        let best = &mut distances[*digit as usize];
+        *best = best.min(distance);
+    }
+    let mut ranked: Vec<usize> = (1..=9).collect();
+    ranked.sort_by(|&a, &b| distances[a].total_cmp(&distances[b]));
+    let (best, second) = (distances[ranked[0]], distances[ranked[1]]);
+    (ranked[0] as u8, best > DISTANT || second - best < AMBIGUOUS)
+}
+
+/// Reads the givens of a sudoku from a screenshot or photo.
+pub fn scan_image(image: &GrayImage) -> Result<Scan> {
+    let mut image = if image.width().max(image.height()) > MAX_SIZE {
+        let scale = MAX_SIZE as f64 / image.width().max(image.height()) as f64;
+        imageops::resize(
+            image,
+            (image.width() as f64 * scale) as u32,
+            (image.height() as f64 * scale) as u32,
+            imageops::FilterType::Triangle,
+        )
+    } else {
+        image.clone()
+    };
+    // light digits on a dark background, as in dark themes
+    let mean = image.pixels().map(|p| p[0] as f64).sum::<f64>() / image.len().max(1) as f64;
+    if mean < 128.0 {
+        imageops::invert(&mut image);
+    }
+    let square = straighten(&image)?;
+    let templates = templates();
+    let mut scan = Scan {
+        grid: [[None; 9]; 9],
+        unsure: [[false; 9]; 9],
+    };
+    for row in 0..9 {
+        for column in 0..9 {
+            if let Some(digit) = digit_image(&square, row, column) {
+                let (digit, unsure) = classify(&digit, &templates);
+                scan.grid[row][column] = Some(digit);
+                scan.unsure[row][column] = unsure;
+            }
+        }
+    }
+    if scan.grid.iter().flatten().all(Option::is_none) {
+        bail!("no digits found in the grid");
+    }
+    Ok(scan)
+}
+
+/// Reads the givens of a sudoku from a PNG or JPEG file.
+pub fn scan(path: &Path) -> Result<Scan> {
+    let image = image::open(path)
+        .wrap_err_with(|| format!("failed to read image {}", path.display()))?
+        .to_luma8();
+    scan_image(&image).wrap_err_with(|| format!("failed to read a sudoku from {}", path.display()))
+}
+
+/// Prints the puzzle read from an image, and on stderr the digits that
+/// should be checked.
+pub fn run(args: &ScanArgs) -> Result<()> {
+    let scan = scan(&args.image)?;
+    println!("{}", format_grid(&scan.grid));
+    for (row, cells) in scan.unsure.iter().enumerate() {
+        for (column, _) in cells.iter().enumerate().filter(|(_, unsure)| **unsure) {
+            if let Some(digit) = scan.grid[row][column] {
+                eprintln!("R{}C{} may not be a {}", row + 1, column + 1, digit);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use image::Luma;
+
+    use sudoku_core::format::parse_grid;
+
+    #[test]
+    fn test_scan() {
+        let grid = parse_grid(
+            "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79",
+        )
+        .unwrap();
+        let templates = templates();
+        assert_eq!(templates.len(), 36);
+
+        // the puzzle drawn flat, with the digits of the first font
+        let (cell, margin) = (36, 20);
+        let size = 9 * cell + 2 * margin;
+        let mut flat = GrayImage::from_pixel(size, size, Luma([255]));
+        for k in 0..=9 {
+            let width = if k % 3 == 0 { 3 } else { 1 };
+            for a in margin..margin + 9 * cell + width {
+                for b in margin + k * cell..margin + k * cell + width {
+                    flat.put_pixel(a, b, Luma([0]));
+                    flat.put_pixel(b, a, Luma([0]));
+                }
+            }
+        }
+        for (i, row) in grid.iter().enumerate() {
+            for (j, digit) in row.iter().enumerate() {
+                let Some(digit) = digit else {
+                    continue;
+                };
+                let (_, pixels) = templates[*digit as usize - 1];
+                let (left, top) = (margin + j as u32 * cell + 9, margin + i as u32 * cell + 6);
+                for y in 0..24 {
+                    for x in 0..18 {
+                        if pixels[(y * 2 / 3) * WIDTH + x * 2 / 3] > 0.0 {
+                            flat.put_pixel(left + x as u32, top + y as u32, Luma([40]));
+                        }
+                    }
+                }
+            }
+        }
+        // and photographed at an angle
+        let h = homography(
+            size as f64,
+            [(40.0, 30.0), (420.0, 50.0), (440.0, 430.0), (20.0, 410.0)],
+        );
+        let mut photo = GrayImage::from_pixel(460, 460, Luma([230]));
+        for y in 0..2 * size {
+            for x in 0..2 * size {
+                let (u, v) = (x as f64 / 2.0 + 0.25, y as f64 / 2.0 + 0.25);
+                let (px, py) = project(&h, (u, v));
+                photo.put_pixel(px as u32, py as u32, *flat.get_pixel(x / 2, y / 2));
+            }
+        }
+        assert_eq!(scan_image(&photo).unwrap().grid, grid);
+        imageops::invert(&mut photo);
+        assert_eq!(scan_image(&photo).unwrap().grid, grid);
+
+        let blank = GrayImage::from_pixel(100, 100, Luma([255]));
+        assert!(scan_image(&blank).is_err());
+    }
+}
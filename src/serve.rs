@@ -0,0 +1,460 @@
+use std::{
+    io::Read,
+    sync::{
+        mpsc::{self, RecvTimeoutError},
+        Arc, Condvar, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use color_eyre::{eyre::eyre, Result};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use sudoku_core::{
+    format::format_grid,
+    generate::{self, Difficulty},
+    native,
+    solver::{self, Backend, SolverConfig, SolverError},
+    verify::{self, VerifyError},
+    Grid,
+};
+
+use crate::{cli::ServeArgs, config::Config, fpuzzles};
+
+/// Largest request body read, in bytes.
+const MAX_BODY: u64 = 1 << 20;
+/// Largest `limit` a count may ask for.
+const MAX_COUNT: usize = 1000;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServeConfig {
+    pub address: String,
+    /// Seconds a request may take at most; a request can ask for less with
+    /// `?time_limit=`.
+    pub time_limit: f64,
+    /// External solver processes run at the same time at most; requests
+    /// wait for one to finish until their time is up.
+    pub max_solvers: usize,
+    /// Requests handled at the same time.
+    pub threads: usize,
+}
+
+impl Default for ServeConfig {
+    fn default() -> Self {
+        Self {
+            address: "127.0.0.1:8080".to_string(),
+            time_limit: 10.0,
+            max_solvers: 2,
+            threads: 4,
+        }
+    }
+}
+
+/// A failed request, answered with the status code and the message.
+#[derive(Debug, PartialEq)]
+struct Failure(u16, String);
+
+/// Solver processes that may still be started.
+struct Slots {
+    free: Mutex<usize>,
+    freed: Condvar,
+}
+
+/// A taken slot, given back when the solver process is done.
+struct Slot(Arc<Slots>);
+
+impl Slots {
+    /// Waits for a free slot until the deadline at most.
+    fn take(slots: &Arc<Slots>, deadline: Instant) -> Option<Slot> {
+        let mut free = slots.free.lock().unwrap_or_else(|err| err.into_inner());
+        while *free == 0 {
+            let left = deadline.checked_duration_since(Instant::now())?;
+            free = slots
+                .freed
+                .wait_timeout(free, left)
+                .unwrap_or_else(|err| err.into_inner())
+                .0;
+        }
+        *free -= 1;
+        Some(Slot(slots.clone()))
+    }
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        *self.0.free.lock().unwrap_or_else(|err| err.into_inner()) += 1;
+        self.0.freed.notify_one();
+    }
+}
+
+struct Service {
+    config: ServeConfig,
+    solver: SolverConfig,
+    slots: Arc<Slots>,
+}
+
+/// The value of a query parameter.
+fn parameter<'a>(query: &'a str, name: &str) -> Option<&'a str> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find_map(|(key, value)| (key == name).then_some(value))
+}
+
+/// The puzzle of a request body: the text itself, or the `puzzle` field
+/// of a JSON object, in any format a paste takes.
+fn read_puzzle(body: &str) -> Result<fpuzzles::Import, Failure> {
+    let text = match serde_json::from_str::<Value>(body) {
+        Ok(Value::Object(object)) if object.contains_key("puzzle") => match &object["puzzle"] {
+            Value::String(text) => text.clone(),
+            _ => return Err(Failure(400, "`puzzle` must be a string".to_string())),
+        },
+        _ => body.to_string(),
+    };
+    fpuzzles::read_puzzle(&text).map_err(|err| Failure(400, format!("not a puzzle: {}", err)))
+}
+
+/// Runs the work on its own thread and gives up on it at the deadline.
+///
+/// The work is not stopped then: it runs to the end in the background, which
+/// for the native searches is soon, as they count no further than
+/// `MAX_COUNT`, and SCIP gets the time left as its own limit.
+fn run_until(
+    deadline: Instant,
+    work: impl FnOnce() -> Result<Value, Failure> + Send + 'static,
+) -> Result<Value, Failure> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = sender.send(work());
+    });
+    match receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+        Ok(result) => result,
+        Err(RecvTimeoutError::Timeout) => Err(Failure(504, "time limit reached".to_string())),
+        Err(RecvTimeoutError::Disconnected) => {
+            Err(Failure(500, "the request stopped unexpectedly".to_string()))
+        }
+    }
+}
+
+fn solve(service: &Service, grid: Grid, deadline: Instant) -> Result<Value, Failure> {
+    let mut config = service.solver.clone();
+    let left = deadline
+        .saturating_duration_since(Instant::now())
+        .as_secs_f64();
+    config.time_limit = Some(config.time_limit.map_or(left, |limit| limit.min(left)));
+    let slot = match config.backend {
+        Backend::Scip => Some(Slots::take(&service.slots, deadline).ok_or_else(|| {
+            Failure(
+                503,
+                "every solver process is busy, try again later".to_string(),
+            )
+        })?),
+        Backend::Native => None,
+    };
+    run_until(deadline, move || {
+        // held until the process is done, even when the request gave up
+        let _slot = slot;
+        let start = Instant::now();
+        match solver::solve_with(&grid, &config, &mut |_| {}) {
+            Ok(solution) => Ok(json!({
+                "status": "solved",
+                "solution": format_grid(&solution),
+                "time_ms": start.elapsed().as_secs_f64() * 1000.0,
+            })),
+            Err(SolverError::Infeasible) => Ok(json!({ "status": "infeasible" })),
+            Err(SolverError::TimeLimit) => Err(Failure(504, "time limit reached".to_string())),
+            Err(err) => Err(Failure(500, err.to_string())),
+        }
+    })
+}
+
+fn count(grid: Grid, query: &str, deadline: Instant) -> Result<Value, Failure> {
+    let limit = match parameter(query, "limit").map(str::parse::<usize>) {
+        None => 2,
+        Some(Ok(limit)) if (1..=MAX_COUNT).contains(&limit) => limit,
+        Some(_) => {
+            return Err(Failure(
+                400,
+                format!("`limit` must be a number from 1 to {}", MAX_COUNT),
+            ))
+        }
+    };
+    run_until(deadline, move || {
+        Ok(json!({ "solutions": native::count(&grid, limit), "limit": limit }))
+    })
+}
+
+fn validate(grid: Grid, deadline: Instant) -> Result<Value, Failure> {
+    run_until(deadline, move || {
+        let errors: Vec<String> = verify::conflicts(&grid)
+            .iter()
+            .map(VerifyError::to_string)
+            .collect();
+        let solutions = if errors.is_empty() {
+            native::count(&grid, 2)
+        } else {
+            0
+        };
+        Ok(json!({
+            "valid": solutions == 1,
+            "solutions": solutions,
+            "errors": errors,
+        }))
+    })
+}
+
+fn rate(grid: Grid, deadline: Instant) -> Result<Value, Failure> {
+    run_until(deadline, move || match native::count(&grid, 2) {
+        0 => Err(Failure(422, "the puzzle has no solution".to_string())),
+        1 => Ok(json!({
+            "difficulty": generate::rate(&grid).to_string(),
+            "clues": grid.iter().flatten().flatten().count(),
+        })),
+        _ => Err(Failure(
+            422,
+            "the puzzle has more than one solution".to_string(),
+        )),
+    })
+}
+
+fn generate(query: &str, deadline: Instant) -> Result<Value, Failure> {
+    let wanted = match parameter(query, "difficulty") {
+        None => None,
+        Some("easy") => Some(Difficulty::Easy),
+        Some("medium") => Some(Difficulty::Medium),
+        Some("hard") => Some(Difficulty::Hard),
+        Some(_) => {
+            return Err(Failure(
+                400,
+                "`difficulty` must be easy, medium or hard".to_string(),
+            ))
+        }
+    };
+    run_until(deadline, move || {
+        // hard puzzles are rare, so several may be made before one fits
+        while Instant::now() < deadline {
+            let grid = generate::generate();
+            let difficulty = generate::rate(&grid);
+            if wanted.is_none_or(|wanted| wanted == difficulty) {
+                return Ok(json!({
+                    "puzzle": format_grid(&grid),
+                    "difficulty": difficulty.to_string(),
+                    "clues": grid.iter().flatten().flatten().count(),
+                }));
+            }
+        }
+        Err(Failure(504, "time limit reached".to_string()))
+    })
+}
+
+/// Answers a request, as the status code and the JSON body.
+fn respond(service: &Service, method: &Method, url: &str, body: &str) -> (u16, Value) {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let result = (|| {
+        let time_limit = match parameter(query, "time_limit").map(str::parse::<f64>) {
+            None => service.config.time_limit,
+            Some(Ok(limit)) if limit > 0.0 => limit.min(service.config.time_limit),
+            Some(_) => {
+                return Err(Failure(
+                    400,
+                    "`time_limit` must be a positive number of seconds".to_string(),
+                ))
+            }
+        };
+        let deadline = Duration::try_from_secs_f64(time_limit)
+            .ok()
+            .and_then(|limit| Instant::now().checked_add(limit))
+            .ok_or_else(|| {
+                Failure(
+                    500,
+                    format!("time limit of {}s is out of range", time_limit),
+                )
+            })?;
+        let endpoint = |body: &str| -> Result<Value, Failure> {
+            let puzzle = read_puzzle(body)?;
+            let mut value = match path {
+                "/solve" => solve(service, puzzle.grid, deadline),
+                "/count" => count(puzzle.grid, query, deadline),
+                "/validate" => validate(puzzle.grid, deadline),
+                _ => rate(puzzle.grid, deadline),
+            }?;
+            if !puzzle.dropped.is_empty() {
+                value["dropped"] = puzzle
+                    .dropped
+                    .into_iter()
+                    .map(|(constraint, count)| (constraint, json!(count)))
+                    .collect();
+            }
+            Ok(value)
+        };
+        match (method, path) {
+            (Method::Post, "/solve" | "/count" | "/validate" | "/rate") => endpoint(body),
+            (Method::Get | Method::Post, "/generate") => generate(query, deadline),
+            (_, "/solve" | "/count" | "/validate" | "/rate" | "/generate") => {
+                Err(Failure(405, format!("{} does not take {}", path, method)))
+            }
+            _ => Err(Failure(404, format!("no endpoint {}", path))),
+        }
+    })();
+    match result {
+        Ok(value) => (200, value),
+        Err(Failure(status, message)) => (status, json!({ "error": message })),
+    }
+}
+
+fn handle(service: &Service, mut request: Request) {
+    let start = Instant::now();
+    let mut body = String::new();
+    let (status, value) = match request.as_reader().take(MAX_BODY).read_to_string(&mut body) {
+        Ok(_) => respond(service, request.method(), request.url(), &body),
+        Err(err) => (400, json!({ "error": format!("unreadable body: {}", err) })),
+    };
+    eprintln!(
+        "{} {} {} {:.1}ms",
+        request.method(),
+        request.url(),
+        status,
+        start.elapsed().as_secs_f64() * 1000.0
+    );
+    let header = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..])
+        .expect("the header is valid");
+    let response = Response::from_string(value.to_string())
+        .with_status_code(status)
+        .with_header(header);
+    // the client may be gone already
+    let _ = request.respond(response);
+}
+
+/// Answers solve, count, validate, rate and generate requests over HTTP
+/// until stopped.
+pub fn run(args: &ServeArgs, config: &Config) -> Result<()> {
+    let mut serve = config.serve.clone();
+    if let Some(address) = &args.address {
+        serve.address = address.clone();
+    }
+    let server = Arc::new(
+        Server::http(&serve.address)
+            .map_err(|err| eyre!("failed to listen on {}: {}", serve.address, err))?,
+    );
+    eprintln!("listening on http://{}", serve.address);
+    let service = Arc::new(Service {
+        slots: Arc::new(Slots {
+            free: Mutex::new(serve.max_solvers.max(1)),
+            freed: Condvar::new(),
+        }),
+        solver: config.solver.clone(),
+        config: serve,
+    });
+    let workers: Vec<_> = (0..service.config.threads.max(1))
+        .map(|_| {
+            let (server, service) = (server.clone(), service.clone());
+            thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    handle(&service, request);
+                }
+            })
+        })
+        .collect();
+    for worker in workers {
+        let _ = worker.join();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn service_with(backend: Backend, max_solvers: usize) -> Service {
+        Service {
+            config: ServeConfig::default(),
+            solver: SolverConfig {
+                backend,
+                ..SolverConfig::default()
+            },
+            slots: Arc::new(Slots {
+                free: Mutex::new(max_solvers),
+                freed: Condvar::new(),
+            }),
+        }
+    }
+
+    #[test]
+    fn test_serve() {
+        let service = service_with(Backend::Native, 1);
+        let puzzle =
+            "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+        let (status, value) = respond(&service, &Method::Post, "/solve", puzzle);
+        assert_eq!(status, 200);
+        assert_eq!(
+            value["solution"],
+            "534678912672195348198342567859761423426853791713924856961537284287419635345286179"
+        );
+        let body = json!({ "puzzle": puzzle }).to_string();
+        let (_, value) = respond(&service, &Method::Post, "/validate", &body);
+        assert_eq!(value["valid"], true);
+        let (_, value) = respond(&service, &Method::Post, "/rate", puzzle);
+        assert_eq!(value["clues"], 30);
+
+        let conflict = format!("55{}", &puzzle[2..]);
+        let (_, value) = respond(&service, &Method::Post, "/validate", &conflict);
+        assert_eq!(value["errors"][0], "row 1 has 5 twice");
+        let (_, value) = respond(&service, &Method::Post, "/solve", &conflict);
+        assert_eq!(value["status"], "infeasible");
+        let empty = ".".repeat(81);
+        let (_, value) = respond(&service, &Method::Post, "/count?limit=10", &empty);
+        assert_eq!(value["solutions"], 10);
+        let (status, _) = respond(&service, &Method::Post, "/rate", &empty);
+        assert_eq!(status, 422);
+
+        let (status, value) = respond(&service, &Method::Get, "/generate?difficulty=easy", "");
+        assert_eq!((status, &value["difficulty"]), (200, &json!("easy")));
+
+        assert_eq!(respond(&service, &Method::Get, "/solve", "").0, 405);
+        assert_eq!(respond(&service, &Method::Post, "/nothing", "").0, 404);
+        assert_eq!(respond(&service, &Method::Post, "/solve", "hello").0, 400);
+        assert_eq!(
+            respond(&service, &Method::Post, "/count?limit=0", puzzle).0,
+            400
+        );
+
+        // with the only solver process taken, a request waits out its time
+        let service = service_with(Backend::Scip, 1);
+        let deadline = Instant::now() + Duration::from_secs(1);
+        let slot = Slots::take(&service.slots, deadline).unwrap();
+        let (status, _) = respond(&service, &Method::Post, "/solve?time_limit=0.1", puzzle);
+        assert_eq!(status, 503);
+        drop(slot);
+        assert!(Slots::take(&service.slots, deadline).is_some());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_solver_time_limit() {
+        use std::{fs, os::unix::fs::PermissionsExt};
+
+        // stands in for a SCIP that stops at its time limit without a solution
+        let path = std::env::temp_dir().join(format!("sudoku-scip-{}.sh", std::process::id()));
+        fs::write(
+            &path,
+            "#!/bin/sh\n\
+             echo 'SCIP Status        : solving was interrupted [time limit reached]'\n\
+             printf '\\nStatistics\\n==========\\n\\n'\n\
+             echo 'SCIP Status        : solving was interrupted [time limit reached]'\n",
+        )
+        .unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        let mut service = service_with(Backend::Scip, 1);
+        service.solver.path = Some(path.clone());
+        let (status, value) = respond(&service, &Method::Post, "/solve", &".".repeat(81));
+        fs::remove_file(path).unwrap();
+        assert_eq!(
+            (status, &value["error"]),
+            (504, &json!("time limit reached"))
+        );
+    }
+}
@@ -0,0 +1,164 @@
+use std::{
+    fs::{self, File},
+    io::{BufReader, BufWriter},
+    path::PathBuf,
+    time::Duration,
+};
+
+use color_eyre::{eyre::WrapErr, Result};
+use serde::{Deserialize, Serialize};
+use sudoku_core::Grid;
+
+use crate::{
+    collection::CollectionState,
+    model::{Position, SolveState},
+    play::Play,
+};
+
+/// Everything needed to pick a puzzle back up in a later sitting.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Session {
+    /// Digits entered by the user.
+    pub puzzel: Grid,
+    /// Pencil marks per cell, bit `k` set for candidate `k`.
+    #[serde(default)]
+    pub marks: [[u16; 9]; 9],
+    /// Result of the last solve, if it found one.
+    pub solution: Option<Grid>,
+    /// Outcome of the last solve.
+    pub result: Option<SolveState>,
+    /// Time the last solve took.
+    pub time: Option<Duration>,
+    pub pos: Position,
+    /// Time spent on this puzzle over all sittings.
+    pub elapsed: Duration,
+    /// The puzzle being played by hand, if any.
+    #[serde(default)]
+    pub play: Option<Play>,
+    /// Progress through the open puzzle collection.
+    #[serde(default)]
+    pub collection: Option<CollectionState>,
+}
+
+/// Location of the session file, `$XDG_DATA_HOME/sudoku/session.json` on Linux.
+pub fn path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("sudoku").join("session.json"))
+}
+
+/// Loads the saved session, if there is one. With `new` the file is not
+/// read at all, so that a corrupted one can't stand in the way.
+pub fn load(new: bool) -> Result<Option<Session>> {
+    load_from(path(), new)
+}
+
+fn load_from(path: Option<PathBuf>, new: bool) -> Result<Option<Session>> {
+    let Some(path) = path.filter(|_| !new) else {
+        return Ok(None);
+    };
+    if !path.exists() {
+        return Ok(None);
+    }
+    let file = File::open(&path)?;
+    let session = serde_json::from_reader(BufReader::new(file)).wrap_err_with(|| {
+        format!(
+            "corrupted session file {}, start with --new to discard it",
+            path.display()
+        )
+    })?;
+    Ok(Some(session))
+}
+
+/// Writes the session to disk, replacing any previous one.
+pub fn save(session: &Session) -> Result<()> {
+    save_to(path(), session)
+}
+
+fn save_to(path: Option<PathBuf>, session: &Session) -> Result<()> {
+    let Some(path) = path else {
+        return Ok(());
+    };
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    // written next to the old file and renamed over it, so that a crash or a
+    // full disk leaves the previous session intact
+    let temp = path.with_extension("json.tmp");
+    let mut writer = BufWriter::new(File::create(&temp)?);
+    serde_json::to_writer(&mut writer, session)?;
+    let file = writer.into_inner().map_err(|err| err.into_error())?;
+    file.sync_all()?;
+    fs::rename(&temp, &path)
+        .wrap_err_with(|| format!("failed to save the session to {}", path.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let mut puzzel = [[None; 9]; 9];
+        puzzel[0][1] = Some(3);
+        puzzel[8][7] = Some(6);
+        let mut play = Play::default();
+        play.givens = puzzel;
+        play.hints = 2;
+        let session = Session {
+            puzzel,
+            marks: [[0; 9]; 9],
+            solution: None,
+            result: Some(SolveState::Infeasible),
+            time: None,
+            pos: Position::Left(4, 2),
+            elapsed: Duration::from_secs(95),
+            play: Some(play),
+            collection: None,
+        };
+
+        let json = serde_json::to_string(&session).unwrap();
+        let restored: Session = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.puzzel, puzzel);
+        assert_eq!(restored.result, Some(SolveState::Infeasible));
+        assert_eq!(restored.pos, Position::Left(4, 2));
+        assert_eq!(restored.elapsed, Duration::from_secs(95));
+        let play = restored.play.unwrap();
+        assert_eq!(play.givens, puzzel);
+        assert_eq!(play.hints, 2);
+    }
+
+    #[test]
+    fn test_corrupted() {
+        let path = std::env::temp_dir().join(format!("sudoku-session-{}.json", std::process::id()));
+        fs::write(&path, "{\"puzzel\": [").unwrap();
+        let err = load_from(Some(path.clone()), false).unwrap_err();
+        assert!(err.to_string().starts_with("corrupted session file"));
+        assert!(load_from(Some(path.clone()), true).unwrap().is_none());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_save() {
+        let path = std::env::temp_dir().join(format!("sudoku-saved-{}.json", std::process::id()));
+        fs::write(&path, "{\"puzzel\": [").unwrap();
+        let mut puzzel = [[None; 9]; 9];
+        puzzel[2][3] = Some(4);
+        let session = Session {
+            puzzel,
+            marks: [[0; 9]; 9],
+            solution: None,
+            result: None,
+            time: None,
+            pos: Position::Left(0, 0),
+            elapsed: Duration::ZERO,
+            play: None,
+            collection: None,
+        };
+        // replaces whatever was there, even a corrupted file
+        save_to(Some(path.clone()), &session).unwrap();
+        let restored = load_from(Some(path.clone()), false).unwrap().unwrap();
+        assert_eq!(restored.puzzel, session.puzzel);
+        assert!(!path.with_extension("json.tmp").exists());
+        fs::remove_file(&path).unwrap();
+    }
+}
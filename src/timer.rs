@@ -0,0 +1,65 @@
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+/// A stopwatch that can be paused, e.g. while the terminal is out of focus.
+///
+/// Only the elapsed time is saved, a restored timer is running.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(from = "Duration", into = "Duration")]
+pub struct Timer {
+    /// Time counted before the last resume.
+    elapsed: Duration,
+    /// When the timer was last resumed, `None` while paused.
+    resumed: Option<Instant>,
+}
+
+impl Timer {
+    /// A running timer that starts counting at `elapsed`.
+    pub fn new(elapsed: Duration) -> Self {
+        Self {
+            elapsed,
+            resumed: Some(Instant::now()),
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+            + self
+                .resumed
+                .map_or(Duration::ZERO, |resumed| resumed.elapsed())
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.resumed.is_none()
+    }
+
+    pub fn pause(&mut self) {
+        self.elapsed = self.elapsed();
+        self.resumed = None;
+    }
+
+    pub fn resume(&mut self) {
+        if self.resumed.is_none() {
+            self.resumed = Some(Instant::now());
+        }
+    }
+}
+
+impl Default for Timer {
+    fn default() -> Self {
+        Self::new(Duration::ZERO)
+    }
+}
+
+impl From<Duration> for Timer {
+    fn from(elapsed: Duration) -> Self {
+        Self::new(elapsed)
+    }
+}
+
+impl From<Timer> for Duration {
+    fn from(timer: Timer) -> Self {
+        timer.elapsed()
+    }
+}
@@ -0,0 +1,244 @@
+use std::{fmt, str::FromStr};
+
+use color_eyre::{eyre::WrapErr, Result};
+
+use sudoku_core::{
+    format::{format_grid, parse_grid, split_line},
+    Grid,
+};
+
+use crate::{batch::read_input, cli::TransformArgs};
+
+/// A change of the grid that turns every valid sudoku into another one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transform {
+    /// Quarter turns clockwise.
+    Rotate(u8),
+    /// Left to right.
+    Mirror,
+    Transpose,
+    /// Two rows of the same band.
+    SwapRows(usize, usize),
+    SwapBands(usize, usize),
+    SwapStacks(usize, usize),
+    /// The new digit for every digit, indexed by digit.
+    Relabel([u8; 10]),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct TransformError(String);
+
+impl Transform {
+    /// Relabels the digits in a random order.
+    pub fn random_relabel() -> Self {
+        let mut digits: Vec<u8> = (1..=9).collect();
+        fastrand::shuffle(&mut digits);
+        let mut labels = [0; 10];
+        labels[1..].copy_from_slice(&digits);
+        Transform::Relabel(labels)
+    }
+
+    /// Where the content of a cell ends up.
+    pub fn cell(&self, row: usize, column: usize) -> (usize, usize) {
+        let swap = |n: usize, a: usize, b: usize| match n {
+            n if n == a => b,
+            n if n == b => a,
+            n => n,
+        };
+        match *self {
+            Transform::Rotate(turns) => {
+                (0..turns % 4).fold((row, column), |(row, column), _| (column, 8 - row))
+            }
+            Transform::Mirror => (row, 8 - column),
+            Transform::Transpose => (column, row),
+            Transform::SwapRows(a, b) => (swap(row, a, b), column),
+            Transform::SwapBands(a, b) => (3 * swap(row / 3, a, b) + row % 3, column),
+            Transform::SwapStacks(a, b) => (row, 3 * swap(column / 3, a, b) + column % 3),
+            Transform::Relabel(_) => (row, column),
+        }
+    }
+
+    pub fn digit(&self, k: u8) -> u8 {
+        match self {
+            Transform::Relabel(labels) => labels[k as usize],
+            _ => k,
+        }
+    }
+
+    /// The transform that undoes this one.
+    pub fn inverse(&self) -> Self {
+        match *self {
+            Transform::Rotate(turns) => Transform::Rotate((4 - turns % 4) % 4),
+            Transform::Relabel(labels) => {
+                let mut inverse = [0; 10];
+                for (k, label) in labels.iter().enumerate() {
+                    inverse[*label as usize] = k as u8;
+                }
+                Transform::Relabel(inverse)
+            }
+            transform => transform,
+        }
+    }
+
+    pub fn grid(&self, grid: &Grid) -> Grid {
+        let mut transformed = [[None; 9]; 9];
+        for (i, row) in grid.iter().enumerate() {
+            for (j, cell) in row.iter().enumerate() {
+                let (x, y) = self.cell(i, j);
+                transformed[x][y] = cell.map(|k| self.digit(k));
+            }
+        }
+        transformed
+    }
+
+    /// Moves and relabels pencil marks, bit `k` set for candidate `k`.
+    pub fn marks(&self, marks: &[[u16; 9]; 9]) -> [[u16; 9]; 9] {
+        let mut transformed = [[0; 9]; 9];
+        for (i, row) in marks.iter().enumerate() {
+            for (j, mask) in row.iter().enumerate() {
+                let (x, y) = self.cell(i, j);
+                transformed[x][y] = (1..=9)
+                    .filter(|k| mask & (1 << k) != 0)
+                    .fold(0, |acc, k| acc | 1 << self.digit(k));
+            }
+        }
+        transformed
+    }
+}
+
+impl fmt::Display for TransformError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for TransformError {}
+
+/// Parses `rotate90`, `rotate180`, `rotate270`, `mirror`, `transpose`,
+/// `swap-rows=1,2`, `swap-bands=1,3`, `swap-stacks=2,3` and
+/// `relabel=918273645`, where the digits are the new labels of 1 to 9.
+impl FromStr for Transform {
+    type Err = TransformError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = |message: &str| TransformError(format!("{} in `{}`", message, s));
+        let (name, argument) = s.split_once('=').unwrap_or((s, ""));
+        let pair = || -> Result<(usize, usize), TransformError> {
+            let (a, b) = argument
+                .split_once(',')
+                .ok_or_else(|| err("expected two numbers"))?;
+            let number = |n: &str| match n.trim().parse::<usize>() {
+                Ok(n) if (1..=9).contains(&n) => Ok(n - 1),
+                _ => Err(err("expected a number from 1 to 9")),
+            };
+            Ok((number(a)?, number(b)?))
+        };
+        let bands = || match pair()? {
+            (a, b) if a < 3 && b < 3 => Ok((a, b)),
+            _ => Err(err("there are only 3 bands and stacks")),
+        };
+        match name {
+            "rotate90" => Ok(Transform::Rotate(1)),
+            "rotate180" => Ok(Transform::Rotate(2)),
+            "rotate270" => Ok(Transform::Rotate(3)),
+            "mirror" => Ok(Transform::Mirror),
+            "transpose" => Ok(Transform::Transpose),
+            "swap-rows" => match pair()? {
+                (a, b) if a / 3 == b / 3 => Ok(Transform::SwapRows(a, b)),
+                _ => Err(err("rows must be in the same band")),
+            },
+            "swap-bands" => bands().map(|(a, b)| Transform::SwapBands(a, b)),
+            "swap-stacks" => bands().map(|(a, b)| Transform::SwapStacks(a, b)),
+            "relabel" => {
+                let mut labels = [0; 10];
+                let mut seen = 0u16;
+                for (k, c) in argument.chars().enumerate() {
+                    let label = c.to_digit(10).filter(|&d| d != 0);
+                    match label {
+                        Some(label) if k < 9 && seen & (1 << label) == 0 => {
+                            labels[k + 1] = label as u8;
+                            seen |= 1 << label;
+                        }
+                        _ => return Err(err("expected the digits 1 to 9 in a new order")),
+                    }
+                }
+                if seen.count_ones() != 9 {
+                    return Err(err("expected the digits 1 to 9 in a new order"));
+                }
+                Ok(Transform::Relabel(labels))
+            }
+            _ => Err(err("unknown transform")),
+        }
+    }
+}
+
+/// Copies the input to stdout with every puzzle transformed, keeping the
+/// rest of each line as it is.
+pub fn run(args: &TransformArgs) -> Result<()> {
+    let input = read_input(args.input.as_deref())?;
+    for (n, line) in input.lines().enumerate() {
+        let Some((puzzle, _)) = split_line(line) else {
+            println!("{}", line);
+            continue;
+        };
+        let mut grid = parse_grid(puzzle).wrap_err_with(|| format!("line {}", n + 1))?;
+        for transform in &args.transforms {
+            grid = transform.grid(&grid);
+        }
+        if args.shuffle {
+            grid = Transform::random_relabel().grid(&grid);
+        }
+        // the puzzle is a slice of the line
+        let start = puzzle.as_ptr() as usize - line.as_ptr() as usize;
+        let end = start + puzzle.len();
+        println!("{}{}{}", &line[..start], format_grid(&grid), &line[end..]);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use sudoku_core::{
+        format::{format_grid, parse_grid},
+        logic,
+    };
+
+    #[test]
+    fn test_transform() {
+        let solution = parse_grid(
+            "534678912672195348198342567859761423426853791713924856961537284287419635345286179",
+        )
+        .unwrap();
+        let transforms = [
+            "rotate90",
+            "rotate180",
+            "mirror",
+            "transpose",
+            "swap-rows=4,6",
+            "swap-bands=1,3",
+            "swap-stacks=2,3",
+            "relabel=918273645",
+        ];
+        for transform in transforms {
+            let transform: Transform = transform.parse().unwrap();
+            let transformed = transform.grid(&solution);
+            assert!(logic::is_solved(&transformed), "{:?}", transform);
+            assert_eq!(transform.inverse().grid(&transformed), solution);
+        }
+        let random = Transform::random_relabel();
+        assert_eq!(random.inverse().grid(&random.grid(&solution)), solution);
+
+        let rotated = Transform::Rotate(1).grid(&solution);
+        assert_eq!(&format_grid(&rotated)[..9], "329748165");
+        let marks =
+            Transform::Relabel([0, 2, 1, 3, 4, 5, 6, 7, 8, 9]).marks(&[[1 << 1 | 1 << 9; 9]; 9]);
+        assert_eq!(marks[0][0], 1 << 2 | 1 << 9);
+
+        assert!("swap-rows=1,4".parse::<Transform>().is_err());
+        assert!("swap-bands=1,4".parse::<Transform>().is_err());
+        assert!("relabel=112345678".parse::<Transform>().is_err());
+        assert!("flip".parse::<Transform>().is_err());
+    }
+}
@@ -1,43 +1,203 @@
+use std::rc::Rc;
+
 use ratatui::{
-    layout::{Alignment, Constraint, Direction, Layout},
-    style::{Color, Modifier, Style},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
     symbols,
-    widgets::{Block, BorderType, Borders, Paragraph},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Clear, Paragraph, Wrap},
     Frame,
 };
+use sudoku_core::{
+    format::format_table,
+    stats::{ProblemSize, Stats},
+};
 
-use crate::model::{Model, Position, RunningState, SolveState};
+use crate::{
+    collection::{Collection, Status},
+    keymap::Action,
+    model::{Message, Model, Position, RunningState, SolveState, Target},
+    play::Play,
+};
 
-pub fn render(model: &Model, frame: &mut Frame) {
-    let overall_layout = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(70), Constraint::Percentage(30)])
-        .split(frame.size());
+/// Screen areas of the grid cells and of the panels around it.
+struct Areas {
+    /// Cells indexed by row, then column.
+    cells: Vec<Rc<[Rect]>>,
+    right: Rc<[Rect]>,
+    /// Everything left of the panels, where the log is drawn over the grid.
+    left: Rect,
+    status: Rect,
+    labels: Option<Labels>,
+}
+
+/// Areas of the row and column labels.
+struct Labels {
+    rows: Rc<[Rect]>,
+    columns: Rc<[Rect]>,
+}
 
+/// Splits `area` into nine parts, the last one slightly larger.
+fn nine(area: Rect, direction: Direction) -> Rc<[Rect]> {
     let mut constraits = vec![Constraint::Percentage(11); 8];
     constraits.push(Constraint::Percentage(12));
+    Layout::default()
+        .direction(direction)
+        .constraints(constraits)
+        .split(area)
+}
+
+fn areas(area: Rect, labels: bool) -> Areas {
+    let vertical_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(1)])
+        .split(area);
 
-    let horizontal_layout = Layout::default()
+    let overall_layout = Layout::default()
         .direction(Direction::Horizontal)
-        .constraints(constraits)
-        .split(overall_layout[0]);
-    let mut layout = Vec::default();
-
-    for i in 0..9 {
-        let mut constraits = vec![Constraint::Percentage(11); 8];
-        constraits.push(Constraint::Percentage(12));
-        layout.push(
-            Layout::default()
-                .direction(Direction::Vertical)
-                .constraints(constraits)
-                .split(horizontal_layout[i]),
+        .constraints([Constraint::Percentage(70), Constraint::Percentage(30)])
+        .split(vertical_layout[0]);
+
+    let mut grid = overall_layout[0];
+    let labels = labels.then(|| {
+        // two columns on the left for the row labels, one line on top for the column labels
+        let inner = Rect {
+            x: grid.x + 2,
+            y: grid.y + 1,
+            width: grid.width.saturating_sub(2),
+            height: grid.height.saturating_sub(1),
+        };
+        let rows = nine(
+            Rect {
+                x: grid.x,
+                width: 2,
+                ..inner
+            },
+            Direction::Vertical,
         );
+        let columns = nine(
+            Rect {
+                y: grid.y,
+                height: 1,
+                ..inner
+            },
+            Direction::Horizontal,
+        );
+        grid = inner;
+        Labels { rows, columns }
+    });
+
+    let cells = nine(grid, Direction::Vertical)
+        .iter()
+        .map(|row| nine(*row, Direction::Horizontal))
+        .collect();
+
+    let right = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(70), Constraint::Percentage(30)])
+        .split(overall_layout[1]);
+
+    Areas {
+        cells,
+        right,
+        left: overall_layout[0],
+        status: vertical_layout[1],
+        labels,
+    }
+}
+
+fn contains(rect: Rect, column: u16, row: u16) -> bool {
+    rect.x <= column && column < rect.right() && rect.y <= row && row < rect.bottom()
+}
+
+/// Maps screen coordinates back to what is drawn there.
+///
+/// For grid cells the candidate is picked by splitting the inside of the cell
+/// into a 3x3 grid, the same way pencil marks are drawn.
+pub fn locate(model: &Model, area: Rect, column: u16, row: u16) -> Option<Target> {
+    let areas = areas(area, model.get_config().labels);
+    for (i, cells) in areas.cells.iter().enumerate() {
+        for (j, cell) in cells.iter().enumerate() {
+            if contains(*cell, column, row) {
+                // skip the top and left border
+                let width = cell.width.saturating_sub(1).max(1);
+                let height = cell.height.saturating_sub(1).max(1);
+                let x = (column.saturating_sub(cell.x + 1) * 3 / width).min(2);
+                let y = (row.saturating_sub(cell.y + 1) * 3 / height).min(2);
+                return Some(Target {
+                    pos: Position::Left(i, j),
+                    candidate: (3 * y + x + 1) as u8,
+                });
+            }
+        }
+    }
+    [Position::RightUp, Position::RightDown]
+        .into_iter()
+        .zip(areas.right.iter())
+        .find(|(_, rect)| contains(**rect, column, row))
+        .map(|(pos, _)| Target { pos, candidate: 0 })
+}
+
+/// Pencil marks of a cell, as a 3x3 block if there is room for it.
+fn marks(model: &Model, i: usize, j: usize, cell: Rect) -> String {
+    let marks = model.get_marks(i, j);
+    let mark = |k: u8| {
+        if marks & (1 << k) != 0 {
+            k.to_string()
+        } else {
+            " ".to_string()
+        }
+    };
+    if cell.height >= 4 && cell.width >= 6 {
+        (0..3)
+            .map(|r| {
+                (1..=3)
+                    .map(|c| mark(3 * r + c))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    } else {
+        (1..=9)
+            .filter(|k| marks & (1 << k) != 0)
+            .map(|k| k.to_string())
+            .collect()
+    }
+}
+
+pub fn render(model: &Model, frame: &mut Frame) {
+    let theme = &model.get_config().theme;
+    let Areas {
+        cells: layout,
+        right: right_layout,
+        left,
+        status,
+        labels,
+    } = areas(frame.size(), model.get_config().labels);
+
+    if let Some(Labels { rows, columns }) = labels {
+        let style = Style::new().add_modifier(Modifier::DIM);
+        for (row, rect) in rows.iter().enumerate() {
+            // the digits sit right below the top border of a cell
+            let label = format!("\n{}", (b'A' + row as u8) as char);
+            frame.render_widget(Paragraph::new(label).style(style), *rect);
+        }
+        for (column, rect) in columns.iter().enumerate() {
+            frame.render_widget(
+                Paragraph::new((column + 1).to_string())
+                    .style(style)
+                    .alignment(Alignment::Center),
+                *rect,
+            );
+        }
     }
 
     // render sudoku part
-    for i in 0..9 {
-        for j in 0..9 {
-            let block = match (i, j) {
+    for (i, cells) in layout.iter().enumerate() {
+        for (j, cell) in cells.iter().enumerate() {
+            // the border pattern is written as (column, row)
+            let block = match (j, i) {
                 (0, 0) => Block::new()
                     .border_set(symbols::border::PLAIN)
                     .borders(Borders::TOP | Borders::LEFT)
@@ -204,8 +364,8 @@ pub fn render(model: &Model, frame: &mut Frame) {
             if let Position::Left(x, y) = model.get_position() {
                 if *x == i && *y == j {
                     style = Style::new()
-                        .fg(Color::Green)
-                        .bg(Color::White)
+                        .fg(theme.cursor_fg)
+                        .bg(theme.cursor_bg)
                         .add_modifier(Modifier::BOLD)
                 }
             }
@@ -213,60 +373,365 @@ pub fn render(model: &Model, frame: &mut Frame) {
                 match state {
                     SolveState::Solved => {
                         style = Style::new()
-                    .fg(Color::Gray)
-                    .bg(Color::LightYellow)
-                    .add_modifier(Modifier::ITALIC);
-                    },
+                            .fg(theme.solved_fg)
+                            .bg(theme.solved_bg)
+                            .add_modifier(Modifier::ITALIC);
+                    }
                     SolveState::Infeasible => {
                         style = Style::new()
-                    .fg(Color::Gray)
-                    .bg(Color::Red)
-                    .add_modifier(Modifier::DIM);
-                    },
+                            .fg(theme.infeasible_fg)
+                            .bg(theme.infeasible_bg)
+                            .add_modifier(Modifier::DIM);
+                    }
                 }
             }
+            if let Some(play) = model.get_play() {
+                if play.is_given(i, j) {
+                    style = style.add_modifier(Modifier::BOLD);
+                } else if play.check && play.is_wrong(&model.get_puzzel(), i, j) {
+                    style = style.fg(theme.error_fg);
+                }
+            }
+            if model.is_unsure(i, j) {
+                style = style.fg(theme.error_fg).add_modifier(Modifier::UNDERLINED);
+            }
+            let mut text = model.get_number(i, j);
+            if text.is_empty() {
+                text = marks(model, i, j, *cell);
+                style = style.add_modifier(Modifier::DIM);
+            }
             frame.render_widget(
-                Paragraph::new(model.get_number(i, j))
+                Paragraph::new(text)
                     .block(block)
                     .style(style)
                     .alignment(Alignment::Center),
-                layout[i][j],
+                *cell,
             );
         }
     }
 
-    let right_layout = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([Constraint::Percentage(70), Constraint::Percentage(30)])
-        .split(overall_layout[1]);
     // render visual part
+    let elapsed = model.get_elapsed();
+    let keys = &model.get_config().keys;
+    let key = |action| keys.key(action).map_or("?".to_string(), |k| k.to_string());
     let mut style = Style::default();
     if let Position::RightUp = model.get_position() {
-        style = style.bg(Color::White);
+        style = style.bg(theme.selected_bg).fg(theme.selected_fg);
+    }
+    let mut para = format!("Press {} to quit\n", key(Action::Quit));
+    if let Some(play) = model.get_play() {
+        para += &format!(
+            "Press {} for a hint\nPress {} to turn checking {}\nPress {} to stop playing",
+            key(Action::Hint),
+            key(Action::Check),
+            if play.check { "off" } else { "on" },
+            key(Action::Play)
+        );
+    } else {
+        para += &format!(
+            "Press {} to {}\nPress {} to play\nPress {}/{}/{} to rotate/mirror/transpose\nPress {}/{}/{} to swap row/band/stack, {} to relabel",
+            key(Action::Solve),
+            if model.in_review() {
+                "confirm the scanned puzzle"
+            } else {
+                "start solving"
+            },
+            key(Action::Play),
+            key(Action::Rotate),
+            key(Action::Mirror),
+            key(Action::Transpose),
+            key(Action::SwapRow),
+            key(Action::SwapBand),
+            key(Action::SwapStack),
+            key(Action::Relabel)
+        );
+    }
+    if model.get_collection().is_some() {
+        para += &format!(
+            "\nPress {} for the collection, {}/{} for the next/previous puzzle",
+            key(Action::Collection),
+            key(Action::Next),
+            key(Action::Previous)
+        );
+    }
+    para += &format!(
+        "\nPress {}/{}/{} to copy the puzzle/solution/both, {} to export it",
+        key(Action::CopyPuzzle),
+        key(Action::CopySolution),
+        key(Action::CopyBoth),
+        key(Action::Export)
+    );
+    para += &format!(
+        "\n\nTime: {:02}:{:02}",
+        elapsed.as_secs() / 60,
+        elapsed.as_secs() % 60
+    );
+    if let Some(stats) = model.get_stats() {
+        para += &format!("\n\n{}", stats_report(stats));
     }
     frame.render_widget(
-        Paragraph::new("Press q to quit\nPress Enter to start solving\n").block(Block::default().borders(Borders::ALL).style(style)),
+        Paragraph::new(para).block(Block::default().borders(Borders::ALL).style(style)),
         right_layout[0],
     );
 
     let mut style = Style::default();
     if let Position::RightDown = model.get_position() {
-        style = style.bg(Color::White).fg(Color::Black);
+        style = style.bg(theme.selected_bg).fg(theme.selected_fg);
     }
-    let para = if let RunningState::Solving = model.get_state() {
-        format!("{} solving ...", model.get_icon().content())
+    let para = if let Some(play) = model.get_play() {
+        play_report(play)
+    } else if let RunningState::Solving = model.get_state() {
+        let mut para = format!("{} solving ...", model.get_icon().content());
+        if let Some(progress) = model.get_progress() {
+            let bound = |b: Option<f64>| b.map_or("-".to_string(), |b| format!("{:e}", b));
+            para += &format!(
+                "\nTime: {}\nNodes: {}\nPrimal: {}\nDual: {}\nGap: {}",
+                progress.time,
+                progress.nodes,
+                bound(progress.primal_bound),
+                bound(progress.dual_bound),
+                progress.gap
+            );
+        }
+        para
     } else if let RunningState::Done(state) = model.get_state() {
         match state {
             SolveState::Solved => format!("Solved in {:?}", model.get_time()),
-            SolveState::Infeasible => format!("Infeasible!"),
+            SolveState::Infeasible => "Infeasible!".to_string(),
         }
+    } else if let RunningState::Failed(message) = model.get_state() {
+        message.clone()
     } else {
         "".to_string()
     };
+    let mut block = Block::default().borders(Borders::ALL);
+    if let RunningState::Failed(_) = model.get_state() {
+        block = block
+            .title("Error")
+            .border_style(Style::new().fg(theme.error_fg));
+    }
     frame.render_widget(
         Paragraph::new(para)
-            .block(Block::default().borders(Borders::ALL))
-            .style(style),
+            .block(block)
+            .style(style)
+            .wrap(Wrap { trim: true }),
         right_layout[1],
     );
+
+    if model.show_log() {
+        render_log(model, frame, left);
+    }
+    if let (true, Some(collection)) = (model.show_collection(), model.get_collection()) {
+        render_collection(model, collection, frame, left);
+    }
+
+    let mut line = Vec::new();
+    if let Position::Left(row, column) = model.get_position() {
+        line.push(format!("R{}C{}", row + 1, column + 1));
+    }
+    if let Some(collection) = model.get_collection() {
+        if let Some(current) = collection.current {
+            line.push(format!(
+                "Puzzle {}/{}",
+                current + 1,
+                collection.entries.len()
+            ));
+        }
+    }
+    let mut spans = vec![Span::raw(line.join("  "))];
+    match model.get_message() {
+        Some(Message::Info(message)) => spans.push(Span::raw(format!("  {}", message))),
+        Some(Message::Error(message)) => spans.push(Span::styled(
+            format!("  {}", message),
+            Style::new().fg(theme.error_fg),
+        )),
+        None => {}
+    }
+    frame.render_widget(Paragraph::new(Line::from(spans)), status);
+}
+
+/// Timer, hints and the last message while playing.
+fn play_report(play: &Play) -> String {
+    let time = play.timer.elapsed();
+    let time = format!("{:02}:{:02}", time.as_secs() / 60, time.as_secs() % 60);
+    if play.finished {
+        return format!("Completed in {} with {} hints!", time, play.hints);
+    }
+    let mut para = format!("Playing {}", time);
+    if play.timer.is_paused() {
+        para += " (paused)";
+    }
+    para += &format!("\nHints: {}", play.hints);
+    if play.check {
+        para += if play.solution.is_some() {
+            "\nChecking entries"
+        } else {
+            "\nChecking once the solution is known"
+        };
+    }
+    if let Some(message) = &play.message {
+        para += &format!("\n\n{}", message);
+    }
+    para
+}
+
+/// Breakdown of the last solve for the side panel.
+fn stats_report(stats: &Stats) -> String {
+    let seconds = |s: Option<f64>| s.map_or("-".to_string(), |s| format!("{:.2}s", s));
+    let size = |size: Option<ProblemSize>, f: fn(ProblemSize) -> u64| {
+        size.map_or("-".to_string(), |size| f(size).to_string())
+    };
+    let mut lines = vec![
+        "Last solve".to_string(),
+        format!("  Model build:  {:.1?}", stats.model_build),
+        format!("  Solver wall:  {:.1?}", stats.solver_wall),
+        format!("  Solving:      {}", seconds(stats.solving_time)),
+        format!("  Presolving:   {}", seconds(stats.presolving_time)),
+        format!("  Parsing:      {:.1?}", stats.parsing),
+        format!(
+            "  Variables:    {} -> {}",
+            size(stats.original, |s| s.variables),
+            size(stats.presolved, |s| s.variables)
+        ),
+        format!(
+            "  Constraints:  {} -> {}",
+            size(stats.original, |s| s.constraints),
+            size(stats.presolved, |s| s.constraints)
+        ),
+        format!(
+            "  B&B nodes:    {}",
+            stats.nodes.map_or("-".to_string(), |n| n.to_string())
+        ),
+    ];
+    if let Some(status) = &stats.status {
+        lines.push(format!("  Status:       {}", status));
+    }
+    lines.join("\n")
+}
+
+fn render_log(model: &Model, frame: &mut Frame, area: Rect) {
+    let keys = &model.get_config().keys;
+    let key = |action| keys.key(action).map_or("?".to_string(), |k| k.to_string());
+    let log = model.get_log();
+    let height = area.height.saturating_sub(2) as usize;
+    let end = log.len().saturating_sub(model.get_log_scroll());
+    let start = end.saturating_sub(height);
+    let title = format!(
+        "Solver log ({}/{} to scroll, {} to close)",
+        key(Action::LogUp),
+        key(Action::LogDown),
+        key(Action::Log)
+    );
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(log[start..end].join("\n"))
+            .block(Block::default().borders(Borders::ALL).title(title)),
+        area,
+    );
+}
+
+fn render_collection(model: &Model, collection: &Collection, frame: &mut Frame, area: Rect) {
+    let theme = &model.get_config().theme;
+    let keys = &model.get_config().keys;
+    let key = |action| keys.key(action).map_or("?".to_string(), |k| k.to_string());
+    let name = collection
+        .path
+        .file_name()
+        .map_or("".into(), |name| name.to_string_lossy());
+    let title = format!(
+        "{} ({} to open, {} to close)",
+        name,
+        key(Action::Solve),
+        key(Action::Collection)
+    );
+    let block = Block::default().borders(Borders::ALL).title(title);
+    let inner = block.inner(area);
+    frame.render_widget(Clear, area);
+    frame.render_widget(block, area);
+
+    let layout = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Min(0), Constraint::Length(23)])
+        .split(inner);
+
+    // keep the selection in view
+    let height = layout[0].height as usize;
+    let start = collection
+        .selected
+        .saturating_sub(height / 2)
+        .min(collection.entries.len().saturating_sub(height));
+    let lines: Vec<Line> = collection
+        .entries
+        .iter()
+        .zip(&collection.status)
+        .enumerate()
+        .skip(start)
+        .take(height)
+        .map(|(index, (entry, status))| {
+            let marker = if collection.current == Some(index) {
+                '>'
+            } else {
+                ' '
+            };
+            let status = match status {
+                Status::Unsolved => "-".to_string(),
+                Status::Solved(time) => format!("solved {:.1?}", time),
+                Status::Infeasible => "infeasible".to_string(),
+            };
+            let meta = match collection.duplicates[index] {
+                Some(original) => format!("same as {}  {}", original + 1, entry.meta),
+                None => entry.meta.clone(),
+            };
+            let text = format!(
+                "{}{:>5} {:>2} clues  {:<16} {}",
+                marker,
+                index + 1,
+                entry.clues(),
+                status,
+                meta
+            );
+            if index == collection.selected {
+                Line::styled(
+                    text,
+                    Style::new().fg(theme.selected_fg).bg(theme.selected_bg),
+                )
+            } else {
+                Line::raw(text)
+            }
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines), layout[0]);
+
+    if let Some(entry) = collection.entries.get(collection.selected) {
+        frame.render_widget(
+            Paragraph::new(format_table(&entry.grid))
+                .block(Block::default().borders(Borders::LEFT)),
+            layout[1],
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::config::Config;
+
+    #[test]
+    fn test_locate() {
+        let area = Rect::new(0, 0, 100, 40);
+        let model = Model::new(Config::default());
+        let areas = areas(area, false);
+        let cell = areas.cells[3][7];
+        // bottom right of the inside of the cell is candidate 9
+        let target = locate(&model, area, cell.right() - 1, cell.bottom() - 1).unwrap();
+        assert_eq!(target.pos, Position::Left(3, 7));
+        assert_eq!(target.candidate, 9);
+        let target = locate(&model, area, cell.x + 1, cell.y + 1).unwrap();
+        assert_eq!(target.candidate, 1);
+
+        let panel = areas.right[1];
+        let target = locate(&model, area, panel.x, panel.y).unwrap();
+        assert_eq!(target.pos, Position::RightDown);
+    }
 }
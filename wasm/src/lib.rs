@@ -0,0 +1,115 @@
+//! JavaScript bindings of the solving core, built for `wasm32-unknown-unknown`.
+//!
+//! Puzzles are passed as text in any layout [`parse_puzzle`] reads and
+//! solutions come back as 81 digits. Everything runs on the native backend,
+//! without SCIP; errors are thrown as their message.
+
+use sudoku_core::{
+    format::{format_grid, parse_puzzle},
+    native,
+    solver::{self, Backend, SolverConfig},
+    verify::{self, VerifyError},
+};
+use wasm_bindgen::prelude::*;
+
+/// Solutions [`count`] goes up to at most, so that a page can't hang on
+/// counting the solutions of a nearly empty grid.
+pub const MAX_COUNT: u32 = 1000;
+
+/// What [`validate`] found out about a puzzle.
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Validation {
+    /// The givens don't clash and the solution is unique.
+    #[wasm_bindgen(readonly)]
+    pub valid: bool,
+    /// Solutions, counted up to 2.
+    #[wasm_bindgen(readonly)]
+    pub solutions: u32,
+    /// Every unit with a digit given twice, as "row 1 has 5 twice".
+    #[wasm_bindgen(readonly)]
+    pub errors: Vec<String>,
+}
+
+/// Solves a puzzle, throwing "infeasible" when it has no solution.
+#[wasm_bindgen]
+pub fn solve(puzzle: &str) -> Result<String, String> {
+    let grid = parse_puzzle(puzzle).map_err(|err| err.to_string())?;
+    let config = SolverConfig {
+        backend: Backend::Native,
+        ..SolverConfig::default()
+    };
+    let solution = solver::solve(&grid, &config).map_err(|err| err.to_string())?;
+    Ok(format_grid(&solution))
+}
+
+/// Counts the solutions of a puzzle, stopping once `limit` are found;
+/// `limit` goes from 1 to [`MAX_COUNT`].
+#[wasm_bindgen]
+pub fn count(puzzle: &str, limit: u32) -> Result<u32, String> {
+    if !(1..=MAX_COUNT).contains(&limit) {
+        return Err(format!("`limit` must be a number from 1 to {}", MAX_COUNT));
+    }
+    let grid = parse_puzzle(puzzle).map_err(|err| err.to_string())?;
+    Ok(native::count(&grid, limit as usize) as u32)
+}
+
+/// Checks that the givens of a puzzle don't clash and that it has exactly
+/// one solution.
+#[wasm_bindgen]
+pub fn validate(puzzle: &str) -> Result<Validation, String> {
+    let grid = parse_puzzle(puzzle).map_err(|err| err.to_string())?;
+    let errors: Vec<String> = verify::conflicts(&grid)
+        .iter()
+        .map(VerifyError::to_string)
+        .collect();
+    let solutions = if errors.is_empty() {
+        native::count(&grid, 2) as u32
+    } else {
+        0
+    };
+    Ok(Validation {
+        valid: solutions == 1,
+        solutions,
+        errors,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_api() {
+        let puzzle =
+            "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+        assert_eq!(
+            solve(puzzle).unwrap(),
+            "534678912672195348198342567859761423426853791713924856961537284287419635345286179"
+        );
+        assert_eq!(count(puzzle, 10), Ok(1));
+        assert_eq!(
+            validate(puzzle),
+            Ok(Validation {
+                valid: true,
+                solutions: 1,
+                errors: vec![],
+            })
+        );
+
+        assert_eq!(count(&".".repeat(81), 10), Ok(10));
+        assert_eq!(
+            count(&".".repeat(81), 4_000_000_000),
+            Err("`limit` must be a number from 1 to 1000".to_string())
+        );
+        assert!(count(puzzle, 0).is_err());
+
+        let clash = format!("55{}", &puzzle[2..]);
+        assert_eq!(solve(&clash), Err("infeasible".to_string()));
+        assert_eq!(
+            validate(&clash).unwrap().errors,
+            vec!["row 1 has 5 twice", "box 1 has 5 twice"]
+        );
+        assert_eq!(solve("hello"), Err("unexpected character `h`".to_string()));
+    }
+}